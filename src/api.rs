@@ -0,0 +1,215 @@
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::time::Duration;
+use ureq;
+
+pub const API_URL: &str = "https://trends.shodan.io";
+pub const SHODAN_API_URL: &str = "https://api.shodan.io";
+pub const API_TIMEOUT: u64 = 90; // in seconds
+
+/// Errors returned by [`TrendsClient`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// API responded with an error status, e.g. 400 Invalid search facet
+    Status { code: u16, message: String },
+    /// Request took longer than the client timeout
+    Timeout,
+    /// Some kind of io/ transport error, e.g. DNS, proxy, TLS
+    Transport(String),
+    /// API responded with a body we can't understand
+    Parse(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Status { message, .. } => write!(f, "{}", message),
+            ApiError::Timeout => write!(f, "Timed out, please try again later."),
+            ApiError::Transport(_) => write!(
+                f,
+                "API request failed, please recheck the network or proxy config."
+            ),
+            ApiError::Parse(_) => write!(f, "Failed to parse API response."),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Total results of a month, e.g. {"month": "2017-06", "count": 19799459}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthCount {
+    pub month: String,
+    pub count: i64,
+}
+
+/// Count of a facet value, e.g. {"value": "Linux", "count": 1234}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetBucket {
+    #[serde(deserialize_with = "deserialize_facet_value")]
+    pub value: String,
+    pub count: i64,
+}
+
+/// Facet buckets of a month, e.g. {"key": "2017-06", "values": [...]}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetMonth {
+    #[serde(rename = "key")]
+    pub month: String,
+    pub values: Vec<FacetBucket>,
+}
+
+/// Response of `/api/v1/search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub total: i64,
+    #[serde(default)]
+    pub matches: Vec<MonthCount>,
+    // Keys are facet names, e.g. "country" from requested "country:10"
+    #[serde(default)]
+    pub facets: BTreeMap<String, Vec<FacetMonth>>,
+}
+
+// Some facet values are number, e.g. port, http.html_hash
+fn deserialize_facet_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        value => Err(de::Error::custom(format!("invalid facet value: {}", value))),
+    }
+}
+
+/// Shodan Trends API client.
+#[derive(Debug, Clone)]
+pub struct TrendsClient {
+    api_url: String,
+    shodan_api_url: String, // Used to list search filters and facets
+    api_key: String,
+    timeout: Duration,
+    proxy: Option<String>, // Proxy environment variables are used if none
+}
+
+impl TrendsClient {
+    pub fn new(api_url: &str, api_key: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_owned(),
+            shodan_api_url: SHODAN_API_URL.to_owned(),
+            api_key: api_key.to_owned(),
+            timeout: Duration::from_secs(API_TIMEOUT),
+            proxy: None,
+        }
+    }
+
+    /// Constructs a client for the public API, can be overridden by `MOCK_API_URL` for testing.
+    pub fn from_env(api_key: &str) -> Self {
+        // Can't define const API_URL from environment variables
+        match env::var("MOCK_API_URL") {
+            Ok(api_url) => Self::new(&api_url, api_key).with_shodan_api_url(&api_url),
+            Err(_) => Self::new(API_URL, api_key),
+        }
+    }
+
+    pub fn with_shodan_api_url(mut self, shodan_api_url: &str) -> Self {
+        self.shodan_api_url = shodan_api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = match proxy.is_empty() {
+            true => None,
+            false => Some(proxy.to_owned()),
+        };
+        self
+    }
+
+    pub fn api_url(&self) -> &str {
+        self.api_url.as_str()
+    }
+
+    /// Search the historical database, blocking until the API responds.
+    pub fn search(&self, query: &str, facets: &str) -> Result<SearchResponse, ApiError> {
+        let body = self.get(
+            &format!("{}/api/v1/search", self.api_url),
+            &[("query", query), ("facets", facets)],
+        )?;
+        parse_search_response(&body)
+    }
+
+    /// Names of search filters, e.g. ssl.cert.subject.cn
+    pub fn search_filters(&self) -> Result<Vec<String>, ApiError> {
+        let body = self.get(
+            &format!("{}/shodan/host/search/filters", self.shodan_api_url),
+            &[],
+        )?;
+        serde_json::from_str(&body).map_err(|err| ApiError::Parse(err.to_string()))
+    }
+
+    /// Names of search facets, e.g. http.title
+    pub fn search_facets(&self) -> Result<Vec<String>, ApiError> {
+        let body = self.get(
+            &format!("{}/shodan/host/search/facets", self.shodan_api_url),
+            &[],
+        )?;
+        serde_json::from_str(&body).map_err(|err| ApiError::Parse(err.to_string()))
+    }
+
+    // Make GET request with API key, return response body
+    fn get(&self, url: &str, params: &[(&str, &str)]) -> Result<String, ApiError> {
+        let mut agent = ureq::AgentBuilder::new().timeout(self.timeout);
+        agent = match &self.proxy {
+            Some(proxy) => agent.proxy(
+                ureq::Proxy::new(proxy).map_err(|err| ApiError::Transport(err.to_string()))?,
+            ),
+            None => agent.try_proxy_from_env(true),
+        };
+        let agent = agent.build();
+        let mut request = agent.get(url);
+        for (param, value) in params {
+            request = request.query(param, value);
+        }
+        let resp = request.query("key", &self.api_key).call();
+
+        match resp {
+            Ok(response) => response
+                .into_string()
+                .map_err(|err| ApiError::Transport(err.to_string())),
+            Err(ureq::Error::Status(code, response)) => {
+                let resp_str = response.into_string().unwrap_or_default();
+                Err(parse_error_response(code, &resp_str))
+            }
+            Err(err) => {
+                if err.to_string().contains("timed out") {
+                    Err(ApiError::Timeout)
+                } else {
+                    Err(ApiError::Transport(err.to_string()))
+                }
+            }
+        }
+    }
+}
+
+pub fn parse_search_response(body: &str) -> Result<SearchResponse, ApiError> {
+    serde_json::from_str(body).map_err(|err| ApiError::Parse(err.to_string()))
+}
+
+// API return defined error response, e.g. {"error": "Invalid search facet"}
+fn parse_error_response(code: u16, body: &str) -> ApiError {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|error| error["error"].as_str().map(|message| message.to_owned()))
+        // Failed to parse, e.g. 503 Service Unavailable
+        .unwrap_or("Search failed, please try again later.".to_string());
+
+    ApiError::Status { code, message }
+}
@@ -1,27 +1,37 @@
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, vec};
 
 use crate::components::Component;
-use serde_json::json;
 use uuid::Uuid;
 
+use crate::api::{ApiError, FacetMonth, SearchResponse, TrendsClient};
+use crate::cache::ResponseCache;
+use crate::completion::{CompletionKind, Completions};
+use crate::components::export_dialog::ExportDialog;
 use crate::components::line_chart::LineChart;
 use crate::components::stateful_list::MultiStatefulList;
 use crate::components::user_input::UserInput;
+use crate::config::Config;
+use crate::keymap::Keymap;
+use crate::range::{parse_month, DateRange};
+use crate::session::Session;
 use crate::util;
 use human_repr::HumanCount;
-use serde_json::{self, Value};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::env;
-use ureq;
 use url::form_urlencoded;
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-const API_TIMEOUT: u64 = 90; // in seconds
+/// Search result sent back from the background API request.
+pub type SearchResult = Result<SearchResponse, ApiError>;
+
+// Session with search results of its saved queries, searched in the background
+type RestoredSession = (Session, Vec<(String, SearchResult)>);
 
 // Trends API data already in right format so we just need a bit mapping, otherwise use create chrono for datetime parsing
 const MONTH_ABBR: [&str; 12] = [
@@ -29,6 +39,10 @@ const MONTH_ABBR: [&str; 12] = [
 ];
 pub const EXIT_ERROR_CODE: i32 = 1;
 pub const EXIT_SUCCESS_CODE: i32 = 0;
+// Used by headless commands so scripts could tell why the search failed
+pub const EXIT_NO_RESULTS_CODE: i32 = 2;
+pub const EXIT_API_ERROR_CODE: i32 = 3; // API rejected the request or returned unknown response
+pub const EXIT_NETWORK_ERROR_CODE: i32 = 4; // Timed out or transport errors
 
 #[derive(Debug, Clone)]
 pub struct Points {
@@ -39,13 +53,269 @@ pub struct Points {
 
 #[derive(Debug, Clone, Default)]
 pub struct Chart {
+    pub name: String, // Facet name of facet charts, e.g. country
     pub datasets: Vec<Points>,
     pub x_bounds: Vec<f64>,
     pub y_bounds: Vec<f64>,
     pub x_ticks: Vec<String>,
     pub y_ticks: Vec<String>,
     pub x_labels: Vec<String>,
-    pub facets: Option<Box<Chart>>,
+    pub months: Vec<String>, // YYYY-MM of X labels, used to align charts of different months range
+    pub facets: Vec<Chart>,  // One chart per requested facet
+}
+
+impl Chart {
+    /// Builds the total chart of a query, and the facet chart if users requested facets.
+    pub fn from_response(
+        label: &str,
+        facets: &str,
+        response: &SearchResponse,
+    ) -> Result<Chart, ApiError> {
+        if response.matches.is_empty() {
+            return Err(ApiError::Parse("Missing monthly matches".to_string()));
+        }
+
+        let mut x_axis_labels: Vec<String> = vec![];
+        let mut months: Vec<String> = vec![];
+        let mut max_y_axis = 0.0;
+        let mut data: Vec<(f64, f64)> = vec![];
+
+        for (x_axis, item) in response.matches.iter().enumerate() {
+            let count = item.count as f64;
+            if count > max_y_axis {
+                max_y_axis = count;
+            }
+
+            // Represent each YYYY-MM as float point data
+            data.push((x_axis as f64, count));
+            x_axis_labels.push(month_label(&item.month)?);
+            months.push(item.month.to_owned());
+        }
+
+        // If users requested facets then generate data for build facets line chart later
+        let mut facets_data: Vec<Chart> = vec![];
+        for name in facet_names(facets) {
+            match response.facets.get(name) {
+                Some(months) => facets_data.push(Chart::from_facet_months(name, months)?),
+                None => {
+                    return Err(ApiError::Parse(format!("Missing facet {}", name)));
+                }
+            }
+        }
+
+        Ok(Chart {
+            datasets: vec![Points {
+                label: label.to_owned(),
+                total: response.total,
+                data,
+            }],
+            x_bounds: vec![0.0, (x_axis_labels.len() - 1) as f64],
+            y_bounds: vec![0.0, max_y_axis],
+            x_ticks: x_ticks(&x_axis_labels),
+            y_ticks: y_ticks(max_y_axis),
+            x_labels: x_axis_labels,
+            months,
+            facets: facets_data,
+            ..Default::default()
+        })
+    }
+
+    // Build one line chart Points for each facet value
+    fn from_facet_months(name: &str, months: &[FacetMonth]) -> Result<Chart, ApiError> {
+        if months.is_empty() {
+            return Err(ApiError::Parse("Missing monthly facets".to_string()));
+        }
+
+        let mut x_axis_labels: Vec<String> = vec![];
+        let mut x_axis_months: Vec<String> = vec![];
+        let mut facet_values: HashMap<String, i64> = HashMap::new();
+        let mut month_value_maps: Vec<HashMap<String, f64>> = vec![];
+        let mut max_y_axis = 0.0;
+        let mut datasets = vec![];
+
+        // Get mappings facet value -> count of each month
+        for item in months {
+            let mut tmp_values: HashMap<String, f64> = HashMap::new();
+
+            for bucket in &item.values {
+                let count = bucket.count as f64;
+                if count > max_y_axis {
+                    max_y_axis = count;
+                }
+
+                *facet_values.entry(bucket.value.clone()).or_insert(0) += bucket.count;
+                tmp_values.insert(bucket.value.clone(), count);
+            }
+
+            month_value_maps.push(tmp_values);
+            x_axis_labels.push(month_label(&item.month)?);
+            x_axis_months.push(item.month.to_owned());
+        }
+
+        for (name, total) in facet_values.iter() {
+            let mut data: Vec<(f64, f64)> = vec![];
+            for (month, maps) in month_value_maps.iter().enumerate() {
+                data.push((month as f64, maps.get(name).cloned().unwrap_or(0.0)));
+            }
+
+            datasets.push(Points {
+                label: name.to_owned(),
+                total: *total,
+                data,
+            });
+        }
+
+        // A bit sorting facet value has most records first
+        datasets.sort_by_key(|points| std::cmp::Reverse(points.total));
+
+        Ok(Chart {
+            name: name.to_owned(),
+            datasets,
+            x_bounds: vec![0.0, (x_axis_labels.len() - 1) as f64],
+            y_bounds: vec![0.0, max_y_axis],
+            x_ticks: x_ticks(&x_axis_labels),
+            y_ticks: y_ticks(max_y_axis),
+            x_labels: x_axis_labels,
+            months: x_axis_months,
+            ..Default::default()
+        })
+    }
+
+    /// Percent share of each facet value in every month, of all facet values of the month.
+    pub fn shares(&self) -> Vec<Points> {
+        let months = self.datasets.first().map_or(0, |points| points.data.len());
+        let totals: Vec<f64> = (0..months)
+            .map(|month| {
+                self.datasets
+                    .iter()
+                    .filter_map(|points| points.data.get(month))
+                    .map(|point| point.1)
+                    .sum()
+            })
+            .collect();
+
+        self.datasets
+            .iter()
+            .map(|points| Points {
+                label: points.label.to_owned(),
+                total: points.total,
+                data: points
+                    .data
+                    .iter()
+                    .zip(&totals)
+                    .map(|((x, y), total)| match *total > 0.0 {
+                        true => (*x, y / total * 100.0),
+                        false => (*x, 0.0),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Get facet names from requested facets, e.g. country:10,org -> [country, org]
+pub fn facet_names(facets: &str) -> Vec<&str> {
+    facets
+        .split(',')
+        .filter_map(|facet| facet.split(':').next())
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Convert YYYY-MM to chart label, e.g. 2017-06 -> Jun 2017
+pub fn month_label(month: &str) -> Result<String, ApiError> {
+    let invalid = || ApiError::Parse(format!("Invalid month {}", month));
+    let (year, month_num) = month.split_once('-').ok_or_else(invalid)?;
+    let index = month_num.parse::<usize>().map_err(|_| invalid())?;
+
+    match index {
+        1..=12 => Ok(format!("{} {}", MONTH_ABBR[index - 1], year)), // Index start from 0
+        _ => Err(invalid()),
+    }
+}
+
+// Just use three labels as current line chart looks weird on too many ticks
+// https://github.com/ratatui-org/ratatui/issues/334#issuecomment-1641459034
+/// First, middle and last month labels of the X axis.
+pub fn x_ticks(x_labels: &[String]) -> Vec<String> {
+    let x_axis_len = x_labels.len();
+    vec![
+        x_labels[0].to_owned(),
+        x_labels[x_axis_len / 2].to_owned(),
+        x_labels[x_axis_len - 1].to_owned(),
+    ]
+}
+
+/// Convert float to human-readable format
+pub fn y_ticks(max_y_axis: f64) -> Vec<String> {
+    vec![
+        String::from("0"),
+        ((max_y_axis / 2.0) as i64).human_count_bare().to_string(),
+        (max_y_axis as i64).human_count_bare().to_string(),
+    ]
+}
+
+/// Same as `y_ticks` but starts from `min_y_axis`, used on zoomed in charts.
+pub fn y_range_ticks(min_y_axis: f64, max_y_axis: f64) -> Vec<String> {
+    vec![
+        (min_y_axis as i64).human_count_bare().to_string(),
+        (((min_y_axis + max_y_axis) / 2.0) as i64)
+            .human_count_bare()
+            .to_string(),
+        (max_y_axis as i64).human_count_bare().to_string(),
+    ]
+}
+
+/// Key used to save charts, e.g. query=nginx&facets=os%3A5
+pub fn encode_query(query: &str, facets: &str) -> String {
+    encode_search(query, facets, &DateRange::default())
+}
+
+/// Key used to save charts of the months range, e.g. query=nginx&facets=&since=2022-01
+pub fn encode_search(query: &str, facets: &str, range: &DateRange) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    serializer
+        .append_pair("query", query)
+        .append_pair("facets", facets);
+    if let Some(since) = &range.since {
+        serializer.append_pair("since", since);
+    }
+    if let Some(until) = &range.until {
+        serializer.append_pair("until", until);
+    }
+    serializer.finish()
+}
+
+/// Get back query and facets from the encoded query.
+pub fn decode_query(encoded_query: &str) -> (String, String) {
+    let mut query = String::new();
+    let mut facets = String::new();
+
+    for (key, val) in form_urlencoded::parse(encoded_query.as_bytes()) {
+        if key == "query" {
+            query = val.into_owned();
+        } else if key == "facets" {
+            facets = val.into_owned();
+        }
+    }
+
+    (query, facets)
+}
+
+/// Get back months range from the encoded query, invalid months are ignored.
+pub fn decode_range(encoded_query: &str) -> DateRange {
+    let mut range = DateRange::default();
+
+    for (key, val) in form_urlencoded::parse(encoded_query.as_bytes()) {
+        if key == "since" {
+            range.since = parse_month(&val).ok();
+        } else if key == "until" {
+            range.until = parse_month(&val).ok();
+        }
+    }
+
+    range
 }
 
 #[derive(Debug)]
@@ -56,27 +326,35 @@ pub struct App {
     pub tick_rate: u64,
     pub ticks: usize, // Used to clear some data after number of ticks
 
-    api_url: String,
-    api_key: String,
+    client: TrendsClient,
+    pub cache: ResponseCache,
     pub no_results: bool,
     pub queries: Vec<String>, // Hold success queries (exclude no results or errored out query)
     pub last_query: String,   // Last submitted query
     pub prev_query: String,
 
     pub charts: BTreeMap<String, Chart>,
+    pub searched: HashMap<String, SystemTime>, // Time of the last search of saved queries
     pub api_error: String,
 
     pub search_input: UserInput,
     pub facets_input: UserInput,
+    pub range_input: UserInput, // Months range, e.g. 2022-01..2023-06
     pub line_chart: LineChart,
     pub saved_queries: MultiStatefulList<String>,
     pub facet_values: MultiStatefulList<String>,
+    pub facets_chart: bool, // Chart of facet values, kept on focusing the chart from facet values
+    pub export_dialog: ExportDialog, // Not switched by Tab, takes all keys on shown
     pub widget_index: usize,
 
-    pub receiver: mpsc::Receiver<Result<ureq::Response, ureq::Error>>,
+    pub config: Config,
+
+    pub receiver: mpsc::Receiver<SearchResult>,
+    completions_receiver: Option<mpsc::Receiver<Completions>>,
+    session_receiver: Option<mpsc::Receiver<RestoredSession>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FacetIndex {
     pub selected: Option<usize>,
     pub selected_indexes: Vec<usize>,
@@ -90,16 +368,14 @@ pub struct AppState {
     pub submitted: bool,
     pub first_render: bool,
     pub facet_indexes: HashMap<String, FacetIndex>, // Saved <query.facet_values, selected_indexes>
+    pub facet_tabs: HashMap<String, usize>,         // Saved <query, selected facet tab>
     pub app_log: String,                            // Application log show at the bottom
-    pub sender: mpsc::Sender<Result<ureq::Response, ureq::Error>>,
+    pub sender: mpsc::Sender<SearchResult>,
+    pub keymap: Keymap, // Keys of actions to handle events and show in the help bar
 }
 
 impl App {
-    pub fn new(
-        query: String,
-        facets: String,
-        receiver: mpsc::Receiver<Result<ureq::Response, ureq::Error>>,
-    ) -> Self {
+    pub fn new(query: String, facets: String, receiver: mpsc::Receiver<SearchResult>) -> Self {
         let api_key = match util::get_api_key() {
             Ok(key) => key,
             Err(_) => {
@@ -107,33 +383,53 @@ impl App {
                 std::process::exit(EXIT_ERROR_CODE);
             }
         };
-        // Can't define const API_URL from environment variables
-        let api_url = env::var("MOCK_API_URL").unwrap_or("https://trends.shodan.io".to_string());
+        Self::with_client(query, facets, receiver, TrendsClient::from_env(&api_key))
+    }
+
+    /// Constructs the app with the API client, e.g. of a test server, the API key isn't read.
+    pub fn with_client(
+        query: String,
+        facets: String,
+        receiver: mpsc::Receiver<SearchResult>,
+        client: TrendsClient,
+    ) -> Self {
+        let completions = Completions::default();
+        let config = Config::default();
 
         let mut app = Self {
             running: true,
             blocking: 0,
             blocking_char: String::from("."),
-            tick_rate: 250,
+            tick_rate: config.tick_rate,
             ticks: 0,
 
-            api_url,
-            api_key,
+            client,
+            cache: ResponseCache::default(),
             queries: vec![],
             last_query: String::new(),
             prev_query: String::new(),
             charts: BTreeMap::new(),
+            searched: HashMap::new(),
             api_error: String::new(),
             no_results: false,
 
             saved_queries: MultiStatefulList::new(),
             facet_values: MultiStatefulList::new(),
+            facets_chart: false,
             line_chart: LineChart::new(),
-            search_input: UserInput::new(query),
-            facets_input: UserInput::new(facets),
+            export_dialog: ExportDialog::new(),
+            search_input: UserInput::new(query)
+                .with_completions(CompletionKind::Filter, completions.filters),
+            facets_input: UserInput::new(facets)
+                .with_completions(CompletionKind::Facet, completions.facets),
+            range_input: UserInput::new(String::new()),
             widget_index: 0,
 
+            config,
+
             receiver,
+            completions_receiver: None,
+            session_receiver: None,
         };
 
         // Default hide some widgets
@@ -146,10 +442,22 @@ impl App {
         app
     }
 
+    /// Apply settings of config.toml, e.g. API timeout and proxy.
+    pub fn configure(&mut self, config: Config) {
+        self.tick_rate = config.tick_rate;
+        self.client = self
+            .client
+            .clone()
+            .with_timeout(Duration::from_secs(config.api_timeout))
+            .with_proxy(&config.proxy);
+        self.config = config;
+    }
+
     pub fn get_widgets(&mut self) -> Vec<&mut dyn Component> {
         vec![
             &mut self.search_input,
             &mut self.facets_input,
+            &mut self.range_input,
             &mut self.saved_queries,
             &mut self.facet_values,
             &mut self.line_chart,
@@ -206,206 +514,40 @@ impl App {
         self.ticks += 1;
 
         // TODO Should we move the process out of tick event, maybe custom update event?
+        if let Some(completions) = self
+            .completions_receiver
+            .as_ref()
+            .and_then(|receiver| receiver.try_recv().ok())
+        {
+            self.search_input.set_completion_names(completions.filters);
+            self.facets_input.set_completion_names(completions.facets);
+            self.completions_receiver = None;
+        }
+
         if let Ok(resp) = self.receiver.try_recv() {
-            let query = self.search_input.get_input().to_owned();
-            let facets = self.facets_input.get_input().trim().to_owned();
+            // Search inputs could be changed while waiting, e.g. restored session
+            let (query, facets) = decode_query(&self.last_query);
+            let range = decode_range(&self.last_query);
 
             match resp {
                 Ok(response) => {
-                    // As resp_json["facets"]["key"] key is dynamic based on user request,
-                    // I din't find a proper way to define JSON response mapping struct for it so parse manually
-                    let resp_str = response.into_string()?;
-                    let resp_json: Result<Value, serde_json::Error> =
-                        serde_json::from_str(&resp_str);
-
-                    match resp_json {
-                        Ok(resp_json) => {
-                            let total = resp_json["total"].as_i64().unwrap();
-                            // No results found
-                            if total == 0 {
-                                self.no_results = true;
-                                // Make sure to release lock before return
-                                self.blocking = 0;
-                            } else {
-                                let mut x_axis: f64 = 0.0;
-                                let mut x_axis_labels: Vec<String> = vec![];
-                                let mut max_y_axis = 0.0;
-                                let mut data: Vec<(f64, f64)> = vec![];
-
-                                for item in resp_json["matches"].as_array().unwrap() {
-                                    let count = item["count"].as_i64().unwrap() as f64;
-                                    if count > max_y_axis {
-                                        max_y_axis = count;
-                                    }
-
-                                    data.push((x_axis, count));
-                                    x_axis += 1.0; // Represent each YYYY-MM as float point data
-
-                                    let month_str = item["month"].as_str().unwrap();
-                                    let parts: Vec<&str> = month_str.split('-').collect();
-                                    x_axis_labels.push(format!(
-                                        "{} {}",
-                                        MONTH_ABBR[parts[1].parse::<usize>().unwrap() - 1], // Index start from 0
-                                        parts[0]
-                                    ));
-                                }
-
-                                // Other chart data
-                                x_axis -= 1.0;
-                                let x_bounds = vec![0.0, x_axis];
-                                let y_bounds = vec![0.0, max_y_axis];
-
-                                // Just use three labels as current line chart looks weird on too many ticks
-                                // https://github.com/ratatui-org/ratatui/issues/334#issuecomment-1641459034
-                                let x_axis_len = x_axis_labels.len();
-                                let x_ticks = vec![
-                                    x_axis_labels[0].to_owned(),
-                                    x_axis_labels[x_axis_len / 2].to_owned(),
-                                    x_axis_labels[x_axis_len - 1].to_owned(),
-                                ];
-                                // Convert float to human-readable format
-                                let y_ticks = vec![
-                                    String::from("0"),
-                                    ((max_y_axis / 2.0) as i64).human_count_bare().to_string(),
-                                    (max_y_axis as i64).human_count_bare().to_string(),
-                                ];
-
-                                // If users requested facets then generate data for build facets line chart later
-                                let facets_data: Option<Box<Chart>> = match !facets.is_empty() {
-                                    true => {
-                                        // TODO Currently, we built chart for only first facet, also the API limit to 1 facet.
-                                        let first_facet = facets
-                                            .split(',')
-                                            .next()
-                                            .unwrap()
-                                            .split(':')
-                                            .next()
-                                            .unwrap();
-
-                                        let mut x_axis: f64 = 0.0;
-                                        let mut x_axis_labels: Vec<String> = vec![];
-                                        let mut facet_values: HashMap<String, i64> = HashMap::new();
-                                        let mut month_value_maps: Vec<HashMap<String, f64>> =
-                                            vec![];
-                                        let mut max_y_axis = 0.0;
-                                        let mut datasets = vec![];
-
-                                        // Get mappings facet value -> count of each month
-                                        for item in
-                                            resp_json["facets"][first_facet].as_array().unwrap()
-                                        {
-                                            let mut tmp_values: HashMap<String, f64> =
-                                                HashMap::new();
-
-                                            for bucket in item["values"].as_array().unwrap() {
-                                                let value = match bucket["value"].as_str() {
-                                                    Some(value) => value.to_owned(),
-                                                    // Some facet is number, e.g. port, http.html_hash
-                                                    None => bucket["value"]
-                                                        .as_i64()
-                                                        .unwrap()
-                                                        .to_string(),
-                                                };
-                                                let count =
-                                                    bucket["count"].as_i64().unwrap() as f64;
-
-                                                if count > max_y_axis {
-                                                    max_y_axis = count;
-                                                }
-
-                                                *facet_values.entry(value.clone()).or_insert(0) +=
-                                                    count as i64;
-                                                tmp_values.insert(value, count);
-                                            }
-
-                                            month_value_maps.push(tmp_values);
-                                            x_axis += 1.0; // Represent each YYYY-MM as float point data
-
-                                            let month_str = item["key"].as_str().unwrap();
-                                            let parts: Vec<&str> = month_str.split('-').collect();
-                                            x_axis_labels.push(format!(
-                                                "{} {}",
-                                                MONTH_ABBR[parts[1].parse::<usize>().unwrap() - 1], // Index start from 0
-                                                parts[0]
-                                            ));
-                                        }
-
-                                        // Construct line chart Points for each facet value
-                                        for (name, total) in facet_values.iter() {
-                                            let mut data: Vec<(f64, f64)> = vec![];
-                                            for (month, maps) in month_value_maps.iter().enumerate()
-                                            {
-                                                data.push((
-                                                    month as f64,
-                                                    maps.get(name).cloned().unwrap_or(0.0),
-                                                ));
-                                            }
-
-                                            datasets.push(Points {
-                                                label: name.to_owned(),
-                                                total: *total,
-                                                data,
-                                            });
-                                        }
-
-                                        x_axis -= 1.0;
-                                        let x_bounds = vec![0.0, x_axis];
-                                        let y_bounds = vec![0.0, max_y_axis];
-
-                                        // Just use three labels as current line chart looks weird on too many ticks
-                                        let x_axis_len = x_axis_labels.len();
-                                        let x_ticks = vec![
-                                            x_axis_labels[0].to_owned(),
-                                            x_axis_labels[x_axis_len / 2].to_owned(),
-                                            x_axis_labels[x_axis_len - 1].to_owned(),
-                                        ];
-                                        let y_ticks = vec![
-                                            String::from("0"),
-                                            ((max_y_axis / 2.0) as i64)
-                                                .human_count_bare()
-                                                .to_string(),
-                                            (max_y_axis as i64).human_count_bare().to_string(),
-                                        ];
-
-                                        // A bit sorting facet value has most records first
-                                        datasets.sort_by(|a, b| b.total.cmp(&a.total));
-
-                                        Some(Box::new(Chart {
-                                            datasets,
-                                            x_bounds,
-                                            y_bounds,
-                                            x_ticks,
-                                            y_ticks,
-                                            x_labels: x_axis_labels,
-                                            ..Default::default()
-                                        }))
-                                    }
-                                    false => None,
-                                };
-
-                                let encoded_query: String =
-                                    form_urlencoded::Serializer::new(String::new())
-                                        .append_pair("query", &query)
-                                        .append_pair("facets", &facets)
-                                        .finish();
+                    // Only keep months in the range
+                    let response = range.filter(&response);
+
+                    // No results found, also no months in the range
+                    if response.total == 0 {
+                        self.no_results = true;
+                        // Clear error message
+                        self.api_error = "".to_string();
+                    } else {
+                        let encoded_query = encode_search(&query, &facets, &range);
 
+                        match Chart::from_response(&encoded_query, &facets, &response) {
+                            Ok(chart) => {
                                 // Save data to display chart
-                                self.charts.insert(
-                                    encoded_query.to_owned(),
-                                    Chart {
-                                        datasets: vec![Points {
-                                            label: encoded_query.to_owned(),
-                                            total,
-                                            data,
-                                        }],
-                                        x_bounds,
-                                        y_bounds,
-                                        x_ticks,
-                                        y_ticks,
-                                        x_labels: x_axis_labels,
-                                        facets: facets_data,
-                                    },
-                                );
+                                self.charts.insert(encoded_query.to_owned(), chart);
+                                self.searched
+                                    .insert(encoded_query.to_owned(), SystemTime::now());
 
                                 // Saved queries to display in sidebar
                                 if !self.queries.contains(&encoded_query) {
@@ -413,35 +555,16 @@ impl App {
                                 }
 
                                 self.no_results = false;
+                                self.api_error = "".to_string();
+                            }
+                            Err(err) => {
+                                self.api_error = err.to_string();
                             }
-
-                            // Clear error message
-                            self.api_error = "".to_string();
-                        }
-                        Err(_) => {
-                            self.api_error = "Failed to parse API response.".to_string();
                         }
                     }
                 }
-                Err(ureq::Error::Status(_, response)) => {
-                    let resp_str = response.into_string()?;
-                    let error: Value = serde_json::from_str(&resp_str).unwrap_or(json!({
-                        // Failed to parse, e.g. 503 Service Unavailable
-                        "error": "Search failed, please try again later.",
-                    }));
-
-                    // API return defined error response
-                    self.api_error = error["error"].as_str().unwrap().to_string();
-                }
                 Err(err) => {
-                    // Some kind of io/transport error
-                    if err.to_string().contains("timed out") {
-                        self.api_error = "Timed out, please try again later.".to_string();
-                    } else {
-                        self.api_error =
-                            "API request failed, please recheck the network or proxy config."
-                                .to_string();
-                    }
+                    self.api_error = err.to_string();
                 }
             };
 
@@ -467,46 +590,193 @@ impl App {
         Ok(())
     }
 
+    /// Snapshot of saved queries and their selections.
+    pub fn session(&self, state: &AppState) -> Session {
+        // Saved queries block shows latest query first
+        let list_query = |index: &usize| self.queries.iter().rev().nth(*index).cloned();
+
+        Session {
+            queries: self.queries.clone(),
+            selected_queries: self
+                .saved_queries
+                .state
+                .selected_indexes()
+                .iter()
+                .filter_map(list_query)
+                .collect(),
+            highlighted_query: self
+                .saved_queries
+                .state
+                .selected()
+                .as_ref()
+                .and_then(list_query),
+            facet_indexes: state.facet_indexes.clone(),
+            facet_tabs: state.facet_tabs.clone(),
+        }
+    }
+
+    /// Search saved queries again (mostly from cache) in the background, their selections are
+    /// restored by [`App::receive_session`] once all queries are searched.
+    pub fn restore_session(&mut self, session: Session) {
+        // Searching on launch replaces it, e.g. --query
+        if let Some(query) = &session.highlighted_query {
+            self.last_query = query.to_owned();
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let client = self.client.clone();
+        let cache = self.cache.clone();
+
+        // Lock application like searching, the UI shows the searching progress
+        self.blocking = 1;
+        thread::spawn(move || {
+            let results = session
+                .queries
+                .iter()
+                .map(|encoded_query| {
+                    let (query, facets) = decode_query(encoded_query);
+                    (
+                        encoded_query.to_owned(),
+                        cache.search(&client, &query, &facets),
+                    )
+                })
+                .collect();
+            let _ = sender.send((session, results));
+        });
+        self.session_receiver = Some(receiver);
+    }
+
+    /// Loads charts of the restored session and their selections if searched, called on ticks.
+    pub fn receive_session(&mut self, state: &mut AppState) {
+        let (session, results) = match self
+            .session_receiver
+            .as_ref()
+            .map(|receiver| receiver.try_recv())
+        {
+            Some(Ok(received)) => received,
+            Some(Err(mpsc::TryRecvError::Empty)) | None => return,
+            Some(Err(mpsc::TryRecvError::Disconnected)) => {
+                self.session_receiver = None;
+                self.blocking = 0;
+                return;
+            }
+        };
+        self.session_receiver = None;
+        self.blocking = 0;
+
+        let mut failed = 0;
+        for (encoded_query, result) in results {
+            let (_, facets) = decode_query(&encoded_query);
+            let range = decode_range(&encoded_query);
+            let chart = result.and_then(|response| {
+                Chart::from_response(&encoded_query, &facets, &range.filter(&response))
+            });
+
+            match chart {
+                Ok(chart) if !chart.x_labels.is_empty() => {
+                    self.charts.insert(encoded_query.to_owned(), chart);
+                    self.searched
+                        .insert(encoded_query.to_owned(), SystemTime::now());
+                    if !self.queries.contains(&encoded_query) {
+                        self.queries.push(encoded_query);
+                    }
+                }
+                _ => failed += 1,
+            }
+        }
+
+        let list_index = |query: &String| {
+            self.queries
+                .iter()
+                .rev()
+                .position(|saved_query| saved_query == query)
+        };
+        let selected_indexes = session
+            .selected_queries
+            .iter()
+            .filter_map(list_index)
+            .collect();
+        let highlighted = session.highlighted_query.as_ref().and_then(list_index);
+
+        self.saved_queries
+            .state
+            .with_selected_indexes(selected_indexes);
+        self.saved_queries.state.select(highlighted);
+        if !self.queries.is_empty() {
+            self.saved_queries.set_hide(false);
+            self.facet_values.set_hide(false);
+            self.line_chart.set_hide(false);
+        }
+
+        state.facet_indexes = session.facet_indexes;
+        state.facet_tabs = session.facet_tabs;
+        if failed > 0 {
+            state.app_log = format!("Failed to restore {} queries", failed);
+        }
+    }
+
+    /// Load history of search inputs from the config directory.
+    pub fn load_history(&mut self) {
+        let data_dir = util::get_data_dir();
+        if !data_dir.is_empty() {
+            let dir = PathBuf::from(format!("{}/history", data_dir));
+            self.search_input.load_history(dir.join("queries"));
+            self.facets_input.load_history(dir.join("facets"));
+        }
+    }
+
+    /// Load filter and facet names to complete in the background, cached in the config directory
+    /// like search responses.
+    pub fn load_completions(&mut self) {
+        let data_dir = util::get_data_dir();
+        if data_dir.is_empty() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let client = self.client.clone();
+        let path = PathBuf::from(format!("{}/cache/completions.json", data_dir));
+        let mode = self.cache.mode();
+        thread::spawn(move || {
+            let _ = sender.send(Completions::load(&client, &path, mode));
+        });
+        self.completions_receiver = Some(receiver);
+    }
+
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 
-    pub fn search(
-        &mut self,
-        sender: mpsc::Sender<Result<ureq::Response, ureq::Error>>,
-    ) -> AppResult<()> {
+    pub fn search(&mut self, sender: mpsc::Sender<SearchResult>) -> AppResult<()> {
         let query = self.search_input.get_input().to_owned();
         let facets = self.facets_input.get_input().trim().to_owned();
-        let encoded_query: String = form_urlencoded::Serializer::new(String::new())
-            .append_pair("query", &query)
-            .append_pair("facets", &facets)
-            .finish();
+        let range = DateRange::parse(self.range_input.get_input());
 
         // Save last submitted query
-        self.last_query = encoded_query;
+        self.last_query = encode_search(
+            &query,
+            &facets,
+            range.as_ref().unwrap_or(&DateRange::default()),
+        );
 
         // Pre validate to skip API call
         if query.is_empty() {
             self.api_error = "Invalid search query".to_string();
+        } else if let Err(err) = range {
+            self.api_error = err;
         } else {
+            self.search_input.submit_message();
+            self.facets_input.submit_message();
+
             // Lock application, delay terminal events
             self.blocking = 1;
-            let api_url = self.api_url.to_owned();
-            let api_key = self.api_key.to_owned();
+            let client = self.client.clone();
+            let cache = self.cache.clone();
 
             // Make API request in the background
             thread::spawn(move || {
-                let agent = ureq::AgentBuilder::new()
-                    .timeout(Duration::from_secs(API_TIMEOUT))
-                    .try_proxy_from_env(true)
-                    .build();
-                let resp: Result<ureq::Response, ureq::Error> = agent
-                    .get(&format!("{}/api/v1/search", api_url))
-                    .query("query", &query)
-                    .query("facets", &facets)
-                    .query("key", &api_key)
-                    .call();
+                let resp = cache.search(&client, &query, &facets);
 
                 // Let self.tick (unblocking function) process API response
                 sender.send(resp).unwrap();
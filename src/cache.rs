@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, create_dir_all, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::api::{ApiError, SearchResponse, TrendsClient};
+use crate::app::encode_query;
+use crate::util;
+
+pub const CACHE_TTL: u64 = 24 * 60 * 60; // in seconds
+
+/// How search responses are cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Load cached response if not expired, otherwise call API and save response
+    Enabled,
+    /// Always call API and save response, e.g. --refresh
+    Refresh,
+    /// Don't load or save anything, e.g. --no-cache
+    Disabled,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    created: u64, // Unix timestamp in seconds
+    response: SearchResponse,
+}
+
+/// On-disk cache of search responses, keyed by encoded query, e.g. query=nginx&facets=os%3A5
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    mode: CacheMode,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(CacheMode::Enabled)
+    }
+}
+
+impl ResponseCache {
+    /// Constructs a cache under the config directory.
+    pub fn new(mode: CacheMode) -> Self {
+        let data_dir = util::get_data_dir();
+        Self {
+            dir: PathBuf::from(format!("{}/cache", data_dir)),
+            ttl: Duration::from_secs(CACHE_TTL),
+            // Can't save anything without home directory
+            mode: match data_dir.is_empty() {
+                true => CacheMode::Disabled,
+                false => mode,
+            },
+        }
+    }
+
+    pub fn with_dir(mut self, dir: PathBuf) -> Self {
+        self.dir = dir;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// Search with cached response if any, only success responses are saved.
+    pub fn search(
+        &self,
+        client: &TrendsClient,
+        query: &str,
+        facets: &str,
+    ) -> Result<SearchResponse, ApiError> {
+        let key = encode_query(query, facets);
+
+        if let Some(response) = self.get(&key) {
+            return Ok(response);
+        }
+
+        let response = client.search(query, facets)?;
+        // Failed to write cache shouldn't fail the search
+        let _ = self.set(&key, &response);
+        Ok(response)
+    }
+
+    /// Get cached response if not expired.
+    pub fn get(&self, key: &str) -> Option<SearchResponse> {
+        if self.mode != CacheMode::Enabled {
+            return None;
+        }
+
+        let mut content = String::new();
+        File::open(self.path(key))
+            .ok()?
+            .read_to_string(&mut content)
+            .ok()?;
+        let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+
+        // Different keys could have same hash
+        if entry.key != key || now().saturating_sub(entry.created) > self.ttl.as_secs() {
+            return None;
+        }
+
+        Some(entry.response)
+    }
+
+    pub fn set(&self, key: &str, response: &SearchResponse) -> Result<(), std::io::Error> {
+        if self.mode == CacheMode::Disabled {
+            return Ok(());
+        }
+
+        create_dir_all(&self.dir)?;
+        let entry = CacheEntry {
+            key: key.to_owned(),
+            created: now(),
+            response: response.to_owned(),
+        };
+        let content = serde_json::to_string(&entry)?;
+
+        // Write to temp file first so we never read a half written file
+        let path = self.path(key);
+        let tmp_path = path.with_extension("tmp");
+        File::create(&tmp_path)?.write_all(content.as_bytes())?;
+        fs::rename(tmp_path, path)
+    }
+
+    // Encoded query could be longer than file name limit, so use its hash
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Stable across Rust versions, unlike std DefaultHasher
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
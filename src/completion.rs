@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::api::TrendsClient;
+use crate::cache::{CacheMode, CACHE_TTL};
+use crate::query::FILTERS;
+
+/// Common facets, https://www.shodan.io/search/facet
+pub const FACETS: [&str; 36] = [
+    "asn",
+    "city",
+    "cloud.provider",
+    "cloud.region",
+    "country",
+    "cpe",
+    "device",
+    "domain",
+    "has_screenshot",
+    "http.component",
+    "http.component_category",
+    "http.favicon.hash",
+    "http.html_hash",
+    "http.status",
+    "http.title",
+    "http.waf",
+    "isp",
+    "link",
+    "org",
+    "os",
+    "port",
+    "product",
+    "region",
+    "screenshot.label",
+    "ssh.hassh",
+    "ssh.type",
+    "ssl.alpn",
+    "ssl.cert.issuer.cn",
+    "ssl.cert.subject.cn",
+    "ssl.cipher.name",
+    "ssl.jarm",
+    "ssl.version",
+    "state",
+    "tag",
+    "version",
+    "vuln",
+];
+
+/// What to complete, decides where the word under the cursor starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// Filter names in search query, e.g. -ssl.cert.su| => ssl.cert.subject.cn:
+    Filter,
+    /// Facet names in comma-separated facets, e.g. os:5,coun| => country:
+    Facet,
+}
+
+impl CompletionKind {
+    /// Byte index of the name being typed before the cursor, `None` if it's not a name.
+    pub fn word_start(&self, before_cursor: &str) -> Option<usize> {
+        let start = match self {
+            CompletionKind::Filter => before_cursor
+                .rfind(char::is_whitespace)
+                .map_or(0, |index| index + 1),
+            CompletionKind::Facet => before_cursor.rfind(',').map_or(0, |index| index + 1),
+        };
+        let word = &before_cursor[start..];
+        let trimmed = match self {
+            CompletionKind::Filter => word.trim_start_matches('-'),
+            CompletionKind::Facet => word.trim_start(),
+        };
+
+        // Already typed the value, e.g. port:4
+        match trimmed.contains(':') || trimmed.contains('"') {
+            true => None,
+            false => Some(before_cursor.len() - trimmed.len()),
+        }
+    }
+}
+
+/// Search filter and facet names to complete, built-in names are used if can't fetch from API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Completions {
+    pub filters: Vec<String>,
+    pub facets: Vec<String>,
+}
+
+impl Default for Completions {
+    fn default() -> Self {
+        Self {
+            filters: FILTERS.iter().map(|name| name.to_string()).collect(),
+            facets: FACETS.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl Completions {
+    /// Load names cached in `path` or fetch from API, fall back to built-in names.
+    ///
+    /// Names are cached like search responses, the expired cache is still used if can't fetch.
+    pub fn load(client: &TrendsClient, path: &Path, mode: CacheMode) -> Self {
+        let mut completions = Self::default();
+
+        let read_cache = || {
+            let age = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+            let cached = fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Completions>(&content).ok())?;
+            Some((cached, age < Duration::from_secs(CACHE_TTL)))
+        };
+        let cached = match mode {
+            CacheMode::Disabled => None,
+            _ => read_cache(),
+        };
+
+        match cached {
+            Some((cached, true)) if mode == CacheMode::Enabled => completions.merge(cached),
+            _ => match (client.search_filters(), client.search_facets()) {
+                (Ok(filters), Ok(facets)) => {
+                    let fetched = Completions { filters, facets };
+                    // Failed to write cache shouldn't fail the completion
+                    if mode != CacheMode::Disabled {
+                        if let Some(dir) = path.parent() {
+                            let _ = create_dir_all(dir);
+                        }
+                        if let Ok(content) = serde_json::to_string(&fetched) {
+                            let _ = fs::write(path, content);
+                        }
+                    }
+                    completions.merge(fetched);
+                }
+                _ => {
+                    if let Some((cached, _)) = cached {
+                        completions.merge(cached);
+                    }
+                }
+            },
+        }
+
+        completions
+    }
+
+    /// Add new names, keep names sorted and unique.
+    pub fn merge(&mut self, other: Completions) {
+        for (names, others) in [
+            (&mut self.filters, other.filters),
+            (&mut self.facets, other.facets),
+        ] {
+            names.extend(others);
+            names.sort();
+            names.dedup();
+        }
+    }
+}
+
+/// Names start with the prefix first, then names contain the prefix, e.g. title => http.title
+pub fn complete<'a>(names: &'a [String], prefix: &str) -> Vec<&'a str> {
+    if prefix.is_empty() {
+        return vec![];
+    }
+
+    let prefix = prefix.to_lowercase();
+    let mut matches: Vec<&str> = names
+        .iter()
+        .filter(|name| name.starts_with(&prefix))
+        .map(|name| name.as_str())
+        .collect();
+    matches.extend(
+        names
+            .iter()
+            .filter(|name| !name.starts_with(&prefix) && name.contains(&prefix))
+            .map(|name| name.as_str()),
+    );
+
+    // Nothing to complete if typed the whole name
+    match matches.as_slice() {
+        [name] if *name == prefix => vec![],
+        _ => matches,
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    app::AppState,
+    components::{user_input::UserInput, Component, KeySymbols},
+    export::{default_path, ExportFormat},
+    keymap::{Action, Keymap},
+};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use crossterm::event::{Event, KeyCode};
+use ratatui::layout::Rect;
+
+/// Popup to choose the format and file to export chart data.
+#[derive(Debug)]
+pub struct ExportDialog {
+    id: Uuid,
+    pub format: ExportFormat,
+    pub path: UserInput,
+    /// The file exists, export again to overwrite it
+    pub overwrite: bool,
+    focused: bool,
+    hidden: bool,
+    area: Rect,
+}
+
+impl Default for ExportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExportDialog {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            format: ExportFormat::default(),
+            path: UserInput::new(String::new()),
+            overwrite: false,
+            focused: false,
+            hidden: true,
+            area: Rect::default(),
+        }
+    }
+
+    /// Show the dialog with a new timestamped file of the last format in the directory.
+    pub fn open(&mut self, dir: &str) {
+        self.path
+            .set_input(&default_path(dir, self.format, SystemTime::now()));
+        self.overwrite = false;
+        self.hidden = false;
+        self.focused = true;
+    }
+
+    pub fn close(&mut self) {
+        self.overwrite = false;
+        self.hidden = true;
+        self.focused = false;
+    }
+
+    // Change the format, also the file extension if the file has extension of the old format
+    fn set_format(&mut self, format: ExportFormat) {
+        let extension = format!(".{}", self.format.extension());
+        if let Some(stem) = self.path.get_input().strip_suffix(&extension) {
+            let path = format!("{}.{}", stem, format.extension());
+            self.path.set_input(&path);
+        }
+        self.format = format;
+        self.overwrite = false;
+    }
+}
+
+impl Component for ExportDialog {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn handle_events(&mut self, event: Event, state: &mut AppState) {
+        if let Event::Key(key_event) = event {
+            match key_event.code {
+                KeyCode::Up | KeyCode::BackTab => self.set_format(self.format.next(true)),
+                KeyCode::Down | KeyCode::Tab => self.set_format(self.format.next(false)),
+                // Ask again on changing the file
+                _ => {
+                    self.path.handle_events(event, state);
+                    self.overwrite = false;
+                }
+            }
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focused
+    }
+
+    fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn set_hide(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    fn help_keys(&self, keymap: &Keymap) -> Vec<String> {
+        [
+            Some(format!("Format [{}{}]", KeySymbols::UP, KeySymbols::DOWN)),
+            Some(format!(
+                "Move cursor [{}{}]",
+                KeySymbols::LEFT,
+                KeySymbols::RIGHT
+            )),
+            keymap.help("Export", &[Action::Confirm]),
+            keymap.help("Cancel", &[Action::Unfocus]),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn set_area(&mut self, area: Rect) {
+        self.area = area;
+    }
+}
@@ -1,12 +1,186 @@
-use crate::{app::AppState, components::Component};
+use crate::{
+    app::AppState,
+    components::{Component, KeySymbols},
+    image::ChartImage,
+    keymap::{Action, Context, Keymap},
+};
 use uuid::Uuid;
 
-use crossterm::event::Event;
+// Can't zoom in to fewer months than this
+const MIN_ZOOM_MONTHS: usize = 3;
+/// Default months of the rolling average.
+pub const ROLLING_MONTHS: usize = 3;
+// Bounds of the rolling average window
+const MIN_ROLLING_MONTHS: usize = 2;
+const MAX_ROLLING_MONTHS: usize = 24;
+
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use human_repr::HumanCount;
+use ratatui::layout::Rect;
+
+/// How values are drawn on the Y axis, counts are still shown as is on the cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YScale {
+    #[default]
+    Linear,
+    /// Logarithmic scale, so small lines aren't flat next to large ones
+    Log,
+    /// Percent of the highest visible value of each line
+    Percent,
+    /// Each line indexed to its first visible month, which is 100
+    Indexed,
+}
+
+/// Series derived from monthly counts, drawn instead of the counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Series {
+    #[default]
+    Counts,
+    /// Change from the previous month
+    MonthOverMonth,
+    /// Percent change from the same month of the previous year
+    YearOverYear,
+    /// Average of the last months
+    Rolling(usize),
+}
+
+impl Series {
+    /// Next series to show, rolling average of `months`.
+    pub fn next(self, months: usize) -> Self {
+        match self {
+            Series::Counts => Series::MonthOverMonth,
+            Series::MonthOverMonth => Series::YearOverYear,
+            Series::YearOverYear => Series::Rolling(months),
+            Series::Rolling(_) => Series::Counts,
+        }
+    }
+
+    /// If values are changes, which could be negative.
+    pub fn is_change(self) -> bool {
+        matches!(self, Series::MonthOverMonth | Series::YearOverYear)
+    }
+
+    /// Short name shown next to the Y axis and in exported columns, empty on counts.
+    pub fn title(self) -> String {
+        match self {
+            Series::Counts => String::new(),
+            Series::MonthOverMonth => "MoM change".to_string(),
+            Series::YearOverYear => "YoY % change".to_string(),
+            Series::Rolling(months) => format!("{}-month average", months),
+        }
+    }
+
+    /// Derived points of monthly counts, X values are month indexes.
+    /// Months without enough previous months have no points.
+    pub fn apply(self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        // Previous months are looked up by X values as points could have gaps
+        let value = |x: f64| {
+            points
+                .iter()
+                .find(|point| point.0 == x)
+                .map(|point| point.1)
+        };
+
+        points
+            .iter()
+            .filter_map(|(x, y)| match self {
+                Series::Counts => Some((*x, *y)),
+                Series::MonthOverMonth => value(x - 1.0).map(|previous| (*x, y - previous)),
+                Series::YearOverYear => value(x - 12.0)
+                    .filter(|previous| *previous > 0.0)
+                    .map(|previous| (*x, (y - previous) / previous * 100.0)),
+                Series::Rolling(months) => {
+                    let values: Vec<f64> = (0..months)
+                        .map_while(|month| value(x - month as f64))
+                        .collect();
+                    match values.len() == months {
+                        true => Some((*x, values.iter().sum::<f64>() / months as f64)),
+                        false => None,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl YScale {
+    pub fn next(self) -> Self {
+        match self {
+            YScale::Linear => YScale::Log,
+            YScale::Log => YScale::Percent,
+            YScale::Percent => YScale::Indexed,
+            YScale::Indexed => YScale::Linear,
+        }
+    }
+
+    /// Short name shown next to the Y axis, empty on linear scale.
+    pub fn title(self) -> &'static str {
+        match self {
+            YScale::Linear => "",
+            YScale::Log => "log",
+            YScale::Percent => "% of max",
+            YScale::Indexed => "first month = 100",
+        }
+    }
+
+    /// Points of a line on this scale, points must be the visible months only.
+    pub fn apply(self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let base = match self {
+            YScale::Percent => points.iter().map(|point| point.1).fold(0.0, f64::max),
+            YScale::Indexed => points
+                .iter()
+                .map(|point| point.1)
+                .find(|y| *y > 0.0)
+                .unwrap_or(0.0),
+            _ => 0.0,
+        };
+
+        points
+            .iter()
+            .map(|(x, y)| match self {
+                YScale::Linear => (*x, *y),
+                YScale::Log => (*x, (y + 1.0).log10()),
+                // Keep lines of all zero values at zero
+                YScale::Percent | YScale::Indexed if base == 0.0 => (*x, 0.0),
+                YScale::Percent | YScale::Indexed => (*x, y / base * 100.0),
+            })
+            .collect()
+    }
+
+    /// Label of the Y axis tick at the scaled value.
+    pub fn tick(self, value: f64) -> String {
+        match self {
+            YScale::Linear => (value as i64).human_count_bare().to_string(),
+            YScale::Log => ((10f64.powf(value) - 1.0).round() as i64)
+                .human_count_bare()
+                .to_string(),
+            YScale::Percent => format!("{:.0}%", value),
+            YScale::Indexed => format!("{:.0}", value),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct LineChart {
     id: Uuid,
     pub data: Vec<Vec<String>>,
+    /// Displayed lines to export as images
+    pub image: ChartImage,
+    /// Index of the month to show values, e.g. on hovering
+    pub cursor: Option<usize>,
+    /// Visible months range on zoomed in, `None` shows the whole history
+    pub view: Option<[usize; 2]>,
+    /// Start month of the range to zoom in, marked by Enter key
+    pub anchor: Option<usize>,
+    pub scale: YScale,
+    pub series: Series,
+    /// Months of the rolling average
+    pub rolling: usize,
+    /// Show stats of the highlighted query next to the chart
+    pub stats: bool,
+    /// Graph area on the last rendering, excludes axes and labels
+    area: Rect,
+    x_bounds: [f64; 2],
     focused: bool,
     hidden: bool,
 }
@@ -22,10 +196,175 @@ impl LineChart {
         Self {
             id: Uuid::new_v4(),
             data: vec![],
+            image: ChartImage::default(),
+            cursor: None,
+            view: None,
+            anchor: None,
+            scale: YScale::Linear,
+            series: Series::Counts,
+            rolling: ROLLING_MONTHS,
+            stats: false,
+            area: Rect::default(),
+            x_bounds: [0.0, 0.0],
             focused: false,
             hidden: false,
         }
     }
+
+    pub fn set_x_bounds(&mut self, x_bounds: [f64; 2]) {
+        self.x_bounds = x_bounds;
+    }
+
+    /// Number of months in the chart data, the first row is the header.
+    pub fn months(&self) -> usize {
+        self.data.len().saturating_sub(1)
+    }
+
+    /// First and last visible months of the chart has `months` months.
+    pub fn window(&self, months: usize) -> (usize, usize) {
+        let last = months.saturating_sub(1);
+        match self.view {
+            Some([start, end]) if start < end && end <= last => (start, end),
+            _ => (0, last),
+        }
+    }
+
+    // Move the cursor by months, starts from the last visible month if no cursor yet
+    fn move_cursor(&mut self, months: isize) {
+        let last = self.months().saturating_sub(1);
+        let cursor = match self.cursor {
+            Some(cursor) => cursor.min(last).saturating_add_signed(months),
+            None => self.window(self.months()).1,
+        };
+        self.set_cursor(cursor.min(last));
+    }
+
+    // Pan the view to keep the cursor visible
+    fn set_cursor(&mut self, cursor: usize) {
+        let (start, end) = self.window(self.months());
+        if cursor < start {
+            self.pan(cursor as isize - start as isize);
+        } else if cursor > end {
+            self.pan((cursor - end) as isize);
+        }
+        self.cursor = Some(cursor);
+    }
+
+    // Shift the view by months, the cursor stays in the view
+    fn pan(&mut self, months: isize) {
+        if self.view.is_none() {
+            return;
+        }
+
+        let (start, end) = self.window(self.months());
+        let len = end - start;
+        let start = start
+            .saturating_add_signed(months)
+            .min(self.months() - 1 - len);
+        let end = start + len;
+        self.view = Some([start, end]);
+
+        if let Some(cursor) = self.cursor {
+            self.cursor = Some(cursor.clamp(start, end));
+        }
+    }
+
+    // Show `len` months around the cursor, or around the center of current view
+    fn zoom(&mut self, len: usize) {
+        let months = self.months();
+        let (start, end) = self.window(months);
+        let len = len.clamp(MIN_ZOOM_MONTHS.min(months), months);
+        if len >= months {
+            self.view = None;
+            return;
+        }
+
+        let center = self
+            .cursor
+            .filter(|cursor| (start..=end).contains(cursor))
+            .unwrap_or((start + end) / 2);
+        let start = center.saturating_sub(len / 2).min(months - len);
+        self.view = Some([start, start + len - 1]);
+    }
+
+    // Mark the start month on first Enter, zoom in to the range on second Enter
+    fn select_range(&mut self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+
+        match self.anchor.take() {
+            Some(anchor) if anchor != cursor => {
+                self.view = Some([anchor.min(cursor), anchor.max(cursor)]);
+            }
+            Some(_) => {}
+            None => self.anchor = Some(cursor),
+        }
+    }
+
+    // Change months of the rolling average, also the current series
+    fn set_rolling(&mut self, months: usize) {
+        self.rolling = months.clamp(MIN_ROLLING_MONTHS, MAX_ROLLING_MONTHS);
+        if let Series::Rolling(_) = self.series {
+            self.series = Series::Rolling(self.rolling);
+        }
+    }
+
+    /// Chart data with columns of the derived series, used to export the chart.
+    pub fn export_data(&self) -> Vec<Vec<String>> {
+        let mut data = self.data.clone();
+        if self.series == Series::Counts || data.is_empty() {
+            return data;
+        }
+
+        for column in 1..self.data[0].len() {
+            // Empty cells if the line has no data of the month
+            let points: Vec<(f64, f64)> = self.data[1..]
+                .iter()
+                .enumerate()
+                .filter_map(|(month, row)| Some((month as f64, row.get(column)?.parse().ok()?)))
+                .collect();
+            let derived = self.series.apply(&points);
+
+            data[0].push(format!(
+                "{} ({})",
+                self.data[0][column],
+                self.series.title()
+            ));
+            for (month, row) in data.iter_mut().skip(1).enumerate() {
+                let value = derived.iter().find(|point| point.0 == month as f64);
+                row.push(value.map_or(String::new(), |point| {
+                    format!("{}", (point.1 * 100.0).round() / 100.0)
+                }));
+            }
+        }
+        data
+    }
+
+    /// Month index drawn at the terminal column.
+    pub fn month_at(&self, column: u16) -> Option<usize> {
+        if column < self.area.left() || column >= self.area.right() {
+            return None;
+        }
+
+        // Same scale as ratatui Chart draws the points
+        let [min, max] = self.x_bounds;
+        let ratio = match self.area.width {
+            0 | 1 => 0.0,
+            width => (column - self.area.x) as f64 / (width - 1) as f64,
+        };
+        Some((min + ratio * (max - min)).round().max(0.0) as usize)
+    }
+
+    /// Terminal column where the month is drawn.
+    pub fn column_of(&self, month: usize) -> u16 {
+        let [min, max] = self.x_bounds;
+        let ratio = match max > min {
+            true => (month as f64 - min) / (max - min),
+            false => 0.0,
+        };
+        self.area.x + (ratio.clamp(0.0, 1.0) * self.area.width.saturating_sub(1) as f64) as u16
+    }
 }
 
 #[allow(unused)]
@@ -34,7 +373,50 @@ impl Component for LineChart {
         self.id
     }
 
-    fn handle_events(&mut self, event: Event, state: &mut AppState) {}
+    fn handle_events(&mut self, event: Event, state: &mut AppState) {
+        if let Event::Key(key_event) = event {
+            // No data to inspect
+            if self.months() == 0 {
+                return;
+            }
+
+            let (start, end) = self.window(self.months());
+            let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+            match state.keymap.action(Context::Chart, &key_event) {
+                Some(Action::ZoomIn) => self.zoom((end - start + 1) / 2),
+                Some(Action::ZoomOut) => self.zoom((end - start + 1) * 2),
+                Some(Action::ResetZoom) => {
+                    self.view = None;
+                    self.anchor = None;
+                }
+                Some(Action::ZoomRange) => self.select_range(),
+                Some(Action::YScale) => self.scale = self.scale.next(),
+                Some(Action::Change) => self.series = self.series.next(self.rolling),
+                Some(Action::Stats) => self.stats = !self.stats,
+                Some(Action::FewerMonths) => self.set_rolling(self.rolling - 1),
+                Some(Action::MoreMonths) => self.set_rolling(self.rolling + 1),
+                _ => match key_event.code {
+                    KeyCode::Left if shift => self.pan(-1),
+                    KeyCode::Right if shift => self.pan(1),
+                    KeyCode::Left => self.move_cursor(-1),
+                    KeyCode::Right => self.move_cursor(1),
+                    KeyCode::Home => self.set_cursor(0),
+                    KeyCode::End => self.set_cursor(self.months() - 1),
+                    _ => {}
+                },
+            }
+        }
+
+        if let Event::Mouse(mouse_event) = event {
+            if let MouseEventKind::Moved | MouseEventKind::Down(MouseButton::Left) =
+                mouse_event.kind
+            {
+                if let Some(month) = self.month_at(mouse_event.column) {
+                    self.cursor = Some(month);
+                }
+            }
+        }
+    }
 
     fn focused(&self) -> bool {
         self.focused
@@ -45,10 +427,52 @@ impl Component for LineChart {
     }
 
     fn set_focus(&mut self, focused: bool) {
+        // Hide the cursor on leaving the chart, keep the zoomed view
+        if self.focused && !focused {
+            self.cursor = None;
+            self.anchor = None;
+        }
         self.focused = focused;
     }
 
     fn set_hide(&mut self, hidden: bool) {
         self.hidden = hidden;
     }
+
+    fn help_keys(&self, keymap: &Keymap) -> Vec<String> {
+        let mut keys = vec![
+            Some(format!(
+                "Move cursor [{}{}]",
+                KeySymbols::LEFT,
+                KeySymbols::RIGHT
+            )),
+            Some(format!(
+                "Pan [{}{}{}]",
+                KeySymbols::SHIFT,
+                KeySymbols::LEFT,
+                KeySymbols::RIGHT
+            )),
+            keymap.help(
+                "Zoom",
+                &[Action::ZoomIn, Action::ZoomOut, Action::ResetZoom],
+            ),
+            keymap.help("Zoom range", &[Action::ZoomRange]),
+            keymap.help("Y scale", &[Action::YScale]),
+            keymap.help("Change", &[Action::Change]),
+            keymap.help("Stats", &[Action::Stats]),
+        ];
+
+        if let Series::Rolling(_) = self.series {
+            keys.push(keymap.help("Average months", &[Action::FewerMonths, Action::MoreMonths]));
+        }
+        keys.into_iter().flatten().collect()
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn set_area(&mut self, area: Rect) {
+        self.area = area;
+    }
 }
@@ -2,8 +2,11 @@ use std::fmt;
 use uuid::Uuid;
 
 use crate::app::AppState;
+use crate::keymap::Keymap;
 use crossterm::event::Event;
+use ratatui::layout::Rect;
 
+pub mod export_dialog;
 pub mod line_chart;
 pub mod stateful_list;
 pub mod user_input;
@@ -75,7 +78,15 @@ pub trait Component {
         false
     }
 
-    fn help_keys(&self) -> Vec<String> {
+    // Keys shown in the help bar, remappable keys are labelled from the keymap
+    fn help_keys(&self, keymap: &Keymap) -> Vec<String> {
         vec![]
     }
+
+    // Area on the last rendering, used to find the widget under the mouse
+    fn area(&self) -> Rect {
+        Rect::default()
+    }
+
+    fn set_area(&mut self, area: Rect) {}
 }
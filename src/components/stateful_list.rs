@@ -1,11 +1,13 @@
 use crate::{
     app::{AppState, FacetIndex},
     components::{Component, KeySymbols},
+    keymap::{Action, Context, Keymap},
     widgets::list::MultiListState,
 };
 use uuid::Uuid;
 
-use crossterm::event::{Event, KeyCode};
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+use ratatui::layout::Rect;
 // use ratatui::widgets::ListState;
 
 // https://github.com/ratatui-org/ratatui/blob/v0.22.0/examples/list.rs
@@ -95,8 +97,12 @@ pub struct MultiStatefulList<T> {
     pub state: MultiListState,
     pub state_key: Option<String>,
     pub items: Vec<T>,
+    pub tabs: Vec<String>, // Group items into tabs, e.g. facet names
+    pub tab_index: usize,
+    pub stacked: bool, // Show facet values as stacked percent share of each month
     pub focused: bool,
     pub hidden: bool,
+    area: Rect,
 }
 
 impl<T> Default for MultiStatefulList<T> {
@@ -112,8 +118,12 @@ impl<T> MultiStatefulList<T> {
             state: MultiListState::default(),
             state_key: None, // Saved selected indexes with the key if exists
             items: vec![],
+            tabs: vec![],
+            tab_index: 0,
+            stacked: false,
             focused: false,
             hidden: false,
+            area: Rect::default(),
         }
     }
 
@@ -125,6 +135,33 @@ impl<T> MultiStatefulList<T> {
         self.state_key = state_key;
     }
 
+    pub fn set_tabs(&mut self, tabs: Vec<String>, tab_index: usize) {
+        self.tab_index = tab_index.min(tabs.len().saturating_sub(1));
+        self.tabs = tabs;
+    }
+
+    /// Key used to save selected indexes, each tab has its own selected indexes
+    pub fn indexes_key(&self) -> Option<String> {
+        match (&self.state_key, self.tabs.get(self.tab_index)) {
+            (Some(state_key), Some(tab)) if self.tabs.len() > 1 => {
+                Some(format!("{}#{}", state_key, tab))
+            }
+            (state_key, _) => state_key.to_owned(),
+        }
+    }
+
+    fn switch_tab(&mut self, reverse: bool) {
+        let tabs_len = self.tabs.len();
+        self.tab_index = match reverse {
+            true => (self.tab_index + tabs_len - 1) % tabs_len,
+            false => (self.tab_index + 1) % tabs_len,
+        };
+
+        // Load selected indexes of new tab on next rendering
+        self.items = vec![];
+        self.state = MultiListState::default();
+    }
+
     fn next(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
@@ -153,6 +190,16 @@ impl<T> MultiStatefulList<T> {
         self.state.select(Some(i));
     }
 
+    // Item index at the terminal row, each item has one line
+    fn item_at(&self, row: u16) -> Option<usize> {
+        // Skip top border
+        let index = self.state.offset() + row.checked_sub(self.area.y + 1)? as usize;
+        match row + 1 < self.area.bottom() && index < self.items.len() {
+            true => Some(index),
+            false => None,
+        }
+    }
+
     fn unselect(&mut self) {
         self.state.select(None);
     }
@@ -168,14 +215,59 @@ impl<T> Component for MultiStatefulList<T> {
     }
 
     fn handle_events(&mut self, event: Event, state: &mut AppState) {
+        let action = match event {
+            Event::Key(key_event) => state.keymap.action(Context::List, &key_event),
+            _ => None,
+        };
+
+        // Switch between tabs
+        if self.tabs.len() > 1 && matches!(action, Some(Action::NextFacet | Action::PreviousFacet))
+        {
+            self.switch_tab(action == Some(Action::PreviousFacet));
+
+            if let Some(state_key) = &self.state_key {
+                state
+                    .facet_tabs
+                    .insert(state_key.to_owned(), self.tab_index);
+            }
+            return;
+        }
+
+        // Switch between lines and share of facet values
+        if !self.tabs.is_empty() && action == Some(Action::Share) {
+            self.stacked = !self.stacked;
+            return;
+        }
+
         if !self.items.is_empty() {
+            if let Event::Mouse(mouse_event) = event {
+                match mouse_event.kind {
+                    // Click to highlight and toggle the item
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        if let Some(index) = self.item_at(mouse_event.row) {
+                            self.state.select(Some(index));
+                            self.toggle();
+                        }
+                    }
+                    MouseEventKind::ScrollDown => {
+                        let index = self.state.selected().map_or(0, |index| index + 1);
+                        self.state.select(Some(index.min(self.items.len() - 1)));
+                    }
+                    MouseEventKind::ScrollUp => {
+                        let index = self.state.selected().unwrap_or(0);
+                        self.state.select(Some(index.saturating_sub(1)));
+                    }
+                    _ => {}
+                }
+            }
+
             if let Event::Key(key_event) = event {
-                match key_event.code {
-                    KeyCode::Left => {
+                match (action, key_event.code) {
+                    (Some(Action::UnselectAll), _) => {
                         self.state.with_selected_indexes(vec![]);
                         self.unselect();
                     }
-                    KeyCode::Right => {
+                    (Some(Action::SelectAll), _) => {
                         self.state
                             .with_selected_indexes(Vec::from_iter(0..self.items.len()));
 
@@ -183,17 +275,17 @@ impl<T> Component for MultiStatefulList<T> {
                             self.state.select(Some(self.items.len() - 1));
                         }
                     }
-                    KeyCode::Down => self.next(),
-                    KeyCode::Up => self.previous(),
-                    KeyCode::Enter => self.toggle(),
+                    (Some(Action::Toggle), _) => self.toggle(),
+                    (_, KeyCode::Down) => self.next(),
+                    (_, KeyCode::Up) => self.previous(),
                     _ => {}
                 }
             }
 
             // Save selected indexes of each facet values
-            if let Some(state_key) = &self.state_key {
+            if let Some(indexes_key) = self.indexes_key() {
                 state.facet_indexes.insert(
-                    state_key.to_owned(),
+                    indexes_key,
                     FacetIndex {
                         selected: self.state.selected(),
                         selected_indexes: self.state.selected_indexes().to_owned(),
@@ -219,15 +311,30 @@ impl<T> Component for MultiStatefulList<T> {
         self.hidden = hidden;
     }
 
-    fn help_keys(&self) -> Vec<String> {
-        vec![
-            format!("Up/ Down [{}{}]", KeySymbols::UP, KeySymbols::DOWN),
-            format!("Toggle [{}]", KeySymbols::ENTER),
-            format!(
-                "Select/ Unselect All [{}{}]",
-                KeySymbols::RIGHT,
-                KeySymbols::LEFT
+    fn help_keys(&self, keymap: &Keymap) -> Vec<String> {
+        let mut keys = vec![
+            Some(format!("Up/ Down [{}{}]", KeySymbols::UP, KeySymbols::DOWN)),
+            keymap.help("Toggle", &[Action::Toggle]),
+            keymap.help(
+                "Select/ Unselect All",
+                &[Action::SelectAll, Action::UnselectAll],
             ),
-        ]
+        ];
+
+        if self.tabs.len() > 1 {
+            keys.push(keymap.help("Switch facets", &[Action::PreviousFacet, Action::NextFacet]));
+        }
+        if !self.tabs.is_empty() {
+            keys.push(keymap.help("Share", &[Action::Share]));
+        }
+        keys.into_iter().flatten().collect()
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn set_area(&mut self, area: Rect) {
+        self.area = area;
     }
 }
@@ -1,10 +1,38 @@
 use crate::{
     app::AppState,
+    completion::{complete, CompletionKind},
     components::{Component, KeySymbols},
+    keymap::{Action, Keymap},
 };
+use std::fs::{self, create_dir_all};
+use std::path::PathBuf;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use uuid::Uuid;
 
-use crossterm::event::{Event, KeyCode};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use ratatui::layout::Rect;
+
+/// Maximum number of messages kept in the history file
+pub const HISTORY_LIMIT: usize = 1000;
+
+/// Reverse incremental search over history, e.g. Ctrl+R in shells
+#[derive(Debug, Default)]
+struct HistorySearch {
+    pattern: String,
+    /// Index of matched message in history
+    index: Option<usize>,
+}
+
+/// Completion popup of names, e.g. filter names
+#[derive(Debug)]
+struct Completion {
+    items: Vec<String>,
+    /// Index of highlighted item
+    index: usize,
+    /// Byte index of the name being completed
+    start: usize,
+}
 
 #[allow(unused)]
 // https://github.com/ratatui-org/ratatui/blob/v0.22.0/examples/user_input.rs
@@ -13,27 +41,54 @@ pub struct UserInput {
     id: Uuid,
     /// Current value of the input box
     input: String,
-    /// Position of cursor in the editor area.
+    /// Position of cursor in the editor area, counted in graphemes
     pub cursor_position: usize,
+    /// First visible grapheme when input is wider than the input box
+    scroll: usize,
     /// History of recorded messages
     messages: Vec<String>,
+    /// Index of the message showing in the input box on navigating history
+    history_index: Option<usize>,
+    /// Current input before navigating history
+    draft: String,
+    history_search: Option<HistorySearch>,
+    /// File to persist history across runs
+    history_file: Option<PathBuf>,
+    completion_kind: Option<CompletionKind>,
+    /// Names to complete with Tab key
+    completion_names: Vec<String>,
+    completion: Option<Completion>,
+    /// Start and name of the popup closed by Esc, Tab switches panels instead of completing it
+    dismissed: Option<(usize, String)>,
     /// If panel on focused by `Tab`
     focused: bool,
     /// The widget is not rendered in the UI
     hidden: bool,
+    /// Input line on the last rendering
+    area: Rect,
 }
 
 #[allow(unused)]
 impl UserInput {
     pub fn new(input: String) -> Self {
-        let cursor_position = input.len();
+        let cursor_position = input.graphemes(true).count();
         Self {
             id: Uuid::new_v4(),
             input,
             cursor_position,
+            scroll: 0,
             messages: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            history_search: None,
+            history_file: None,
+            completion_kind: None,
+            completion_names: Vec::new(),
+            completion: None,
+            dismissed: None,
             focused: false,
             hidden: false,
+            area: Rect::default(),
         }
     }
 
@@ -43,7 +98,57 @@ impl UserInput {
 
     pub fn set_input(&mut self, input: &str) {
         self.input = input.to_owned();
-        self.cursor_position = self.input.len();
+        self.cursor_position = self.graphemes().len();
+    }
+
+    /// Scroll to keep the cursor visible in the input box with `width` columns.
+    pub fn scroll(&mut self, width: usize) {
+        let graphemes = self.graphemes();
+        let columns = |start: usize, end: usize| -> usize {
+            graphemes[start..end].iter().map(|g| g.width()).sum()
+        };
+        // Cursor takes one column at the end of input
+        let cursor_width = graphemes
+            .get(self.cursor_position)
+            .map_or(1, |g| g.width().max(1));
+
+        let mut scroll = self.scroll.min(self.cursor_position);
+        while scroll < self.cursor_position
+            && columns(scroll, self.cursor_position) + cursor_width > width
+        {
+            scroll += 1;
+        }
+        // Show as many characters as possible on deleting
+        while scroll > 0 && columns(scroll - 1, graphemes.len()) < width {
+            scroll -= 1;
+        }
+        self.scroll = scroll;
+    }
+
+    /// Input from the first visible grapheme after scrolling.
+    pub fn visible_input(&self) -> &str {
+        &self.input[self.byte_index(self.scroll)..]
+    }
+
+    /// Display column of the cursor relative to the visible input.
+    pub fn cursor_column(&self) -> u16 {
+        let start = self.byte_index(self.scroll);
+        let end = self.byte_index(self.cursor_position.max(self.scroll));
+        self.input[start..end].width() as u16
+    }
+
+    // Move the cursor to the grapheme drawn at the column of visible input
+    fn move_cursor_to(&mut self, column: u16) {
+        let mut width = 0;
+        let mut cursor = self.scroll;
+        for grapheme in self.graphemes().iter().skip(self.scroll) {
+            width += grapheme.width();
+            if width > column as usize {
+                break;
+            }
+            cursor += 1;
+        }
+        self.cursor_position = self.clamp_cursor(cursor);
     }
 
     fn move_cursor_left(&mut self) {
@@ -57,46 +162,427 @@ impl UserInput {
     }
 
     fn enter_char(&mut self, new_char: char) {
-        self.input.insert(self.cursor_position, new_char);
+        let index = self.byte_index(self.cursor_position) + new_char.len_utf8();
+        self.input.insert(index - new_char.len_utf8(), new_char);
 
-        self.move_cursor_right();
+        // Combining characters join the previous grapheme, e.g. e + \u{301} = é
+        self.cursor_position = self.input[..index].graphemes(true).count();
     }
 
     fn delete_char(&mut self) {
         let is_not_cursor_leftmost = self.cursor_position != 0;
         if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
+            self.delete_range(self.cursor_position - 1, self.cursor_position);
+        }
+    }
 
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
+    // Delete grapheme under the cursor, e.g. Delete key
+    fn delete_next_char(&mut self) {
+        self.delete_range(self.cursor_position, self.cursor_position + 1);
+    }
+
+    // Delete whitespace then the word before the cursor, e.g. Ctrl+W
+    fn delete_previous_word(&mut self) {
+        let graphemes = self.graphemes();
+        let is_space = |g: &str| g.chars().all(char::is_whitespace);
+        let mut start = self.cursor_position;
+        while start > 0 && is_space(graphemes[start - 1]) {
+            start -= 1;
+        }
+        while start > 0 && !is_space(graphemes[start - 1]) {
+            start -= 1;
+        }
+        self.delete_range(start, self.cursor_position);
+    }
+
+    // Move to start of current or previous word, e.g. Alt+B
+    fn move_word_left(&mut self) {
+        let graphemes = self.graphemes();
+        let mut cursor = self.cursor_position;
+        while cursor > 0 && !is_word(graphemes[cursor - 1]) {
+            cursor -= 1;
+        }
+        while cursor > 0 && is_word(graphemes[cursor - 1]) {
+            cursor -= 1;
+        }
+        self.cursor_position = cursor;
+    }
 
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
+    // Move to end of current or next word, e.g. Alt+F
+    fn move_word_right(&mut self) {
+        let graphemes = self.graphemes();
+        let mut cursor = self.cursor_position;
+        while cursor < graphemes.len() && !is_word(graphemes[cursor]) {
+            cursor += 1;
+        }
+        while cursor < graphemes.len() && is_word(graphemes[cursor]) {
+            cursor += 1;
+        }
+        self.cursor_position = cursor;
+    }
 
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    // Delete graphemes in range of indexes, move cursor to start of the range
+    fn delete_range(&mut self, start: usize, end: usize) {
+        let start = self.clamp_cursor(start);
+        let end = self.clamp_cursor(end);
+        if start < end {
+            let range = self.byte_index(start)..self.byte_index(end);
+            self.input.replace_range(range, "");
+            self.cursor_position = start;
         }
     }
 
+    // User-perceived characters, e.g. flags or letters with combining marks
+    fn graphemes(&self) -> Vec<&str> {
+        self.input.graphemes(true).collect()
+    }
+
+    // Cursor position is counted in graphemes, convert to byte index to edit the String
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.input
+            .grapheme_indices(true)
+            .nth(cursor)
+            .map_or(self.input.len(), |(index, _)| index)
+    }
+
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.len())
+        new_cursor_pos.clamp(0, self.graphemes().len())
     }
 
     fn reset_cursor(&mut self) {
         self.cursor_position = 0;
     }
 
-    fn submit_message(&mut self) {
-        // TODO By saving messages, we could load next/ previous messages with Arrow keys
-        self.messages.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
+    pub fn with_completions(mut self, kind: CompletionKind, names: Vec<String>) -> Self {
+        self.completion_kind = Some(kind);
+        self.completion_names = names;
+        self
+    }
+
+    pub fn set_completion_names(&mut self, names: Vec<String>) {
+        self.completion_names = names;
+    }
+
+    /// Names to complete, e.g. known filter names of the search box.
+    pub fn completion_names(&self) -> &[String] {
+        &self.completion_names
+    }
+
+    /// If Tab key could complete the name before the cursor, some longer names start with it and
+    /// its popup wasn't closed, e.g. not port or os which are only part of other names.
+    pub fn can_complete(&self) -> bool {
+        self.candidates().is_some_and(|(start, items)| {
+            let name = &self.input[start..self.byte_index(self.cursor_position)];
+            let prefix = name.to_lowercase();
+            items
+                .iter()
+                .any(|item| item.starts_with(&prefix) && *item != prefix)
+                && self.dismissed != Some((start, name.to_owned()))
+        })
+    }
+
+    /// If showing completion popup.
+    pub fn completing(&self) -> bool {
+        self.completion.is_some()
+    }
+
+    /// Completion items, highlighted index and display column of the name being completed.
+    pub fn completion(&self) -> Option<(&[String], usize, u16)> {
+        self.completion.as_ref().map(|completion| {
+            let start = self.byte_index(self.scroll).min(completion.start);
+            let column = self.input[start..completion.start].width() as u16;
+            (completion.items.as_slice(), completion.index, column)
+        })
+    }
+
+    // Name before the cursor and names start with or contain it
+    fn candidates(&self) -> Option<(usize, Vec<String>)> {
+        let before_cursor = &self.input[..self.byte_index(self.cursor_position)];
+        let start = self.completion_kind?.word_start(before_cursor)?;
+        let items = complete(&self.completion_names, &before_cursor[start..])
+            .into_iter()
+            .map(|name| name.to_owned())
+            .collect();
+        Some((start, items))
+    }
+
+    // Complete the only candidate, otherwise show the popup
+    fn start_completion(&mut self) {
+        self.refresh_completion();
+
+        if self
+            .completion
+            .as_ref()
+            .is_some_and(|completion| completion.items.len() == 1)
+        {
+            self.accept_completion();
+        }
+    }
+
+    // Filter items by the name before the cursor, close popup if nothing matched
+    fn refresh_completion(&mut self) {
+        self.completion = match self.candidates() {
+            Some((start, items)) if !items.is_empty() => Some(Completion {
+                items,
+                index: 0,
+                start,
+            }),
+            _ => None,
+        };
+    }
+
+    // Replace name before the cursor with highlighted item, e.g. ssl.cert.su => ssl.cert.subject.cn:
+    fn accept_completion(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            let name = format!("{}:", completion.items[completion.index]);
+            let end = self.byte_index(self.cursor_position);
+            self.input.replace_range(completion.start..end, &name);
+            self.cursor_position = self.input[..completion.start + name.len()]
+                .graphemes(true)
+                .count();
+        }
+    }
+
+    fn handle_completion(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let Some(completion) = &mut self.completion else {
+            return;
+        };
+        let len = completion.items.len();
+
+        match code {
+            KeyCode::Tab | KeyCode::Down => completion.index = (completion.index + 1) % len,
+            KeyCode::Char('n') | KeyCode::Char('N') if modifiers == KeyModifiers::CONTROL => {
+                completion.index = (completion.index + 1) % len;
+            }
+            KeyCode::BackTab | KeyCode::Up => completion.index = (completion.index + len - 1) % len,
+            KeyCode::Char('p') | KeyCode::Char('P') if modifiers == KeyModifiers::CONTROL => {
+                completion.index = (completion.index + len - 1) % len;
+            }
+            KeyCode::Enter => self.accept_completion(),
+            KeyCode::Esc => {
+                let start = completion.start;
+                let end = self.byte_index(self.cursor_position);
+                self.dismissed = Some((start, self.input[start..end].to_owned()));
+                self.completion = None;
+            }
+            KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete => {
+                self.handle_key(code, modifiers);
+                self.refresh_completion();
+            }
+            _ => {
+                self.completion = None;
+                self.handle_key(code, modifiers);
+            }
+        }
+    }
+
+    /// Load history from file, new messages will be saved to this file.
+    pub fn load_history(&mut self, path: PathBuf) {
+        if let Ok(content) = fs::read_to_string(&path) {
+            self.messages = content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.to_owned())
+                .collect();
+        }
+        self.history_file = Some(path);
+    }
+
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+
+    /// Record current input to history, skip empty or same as previous message.
+    pub fn submit_message(&mut self) {
+        self.history_index = None;
+        self.history_search = None;
+
+        let message = self.input.trim();
+        if message.is_empty() || self.messages.last().map(|last| last.as_str()) == Some(message) {
+            return;
+        }
+        self.messages.push(message.to_owned());
+        if self.messages.len() > HISTORY_LIMIT {
+            self.messages.drain(..self.messages.len() - HISTORY_LIMIT);
+        }
+
+        // Failed to save history shouldn't interrupt searching
+        if let Some(path) = &self.history_file {
+            if let Some(dir) = path.parent() {
+                let _ = create_dir_all(dir);
+            }
+            let _ = fs::write(path, self.messages.join("\n") + "\n");
+        }
+    }
+
+    /// If navigating history with Up/ Down keys.
+    pub fn browsing_history(&self) -> bool {
+        self.history_index.is_some()
+    }
+
+    /// Show previous message, return false if there's no older message.
+    pub fn previous_message(&mut self) -> bool {
+        let index = match self.history_index {
+            Some(0) => return false,
+            Some(index) => index - 1,
+            None if self.messages.is_empty() => return false,
+            None => {
+                self.draft = self.input.clone();
+                self.messages.len() - 1
+            }
+        };
+
+        self.history_index = Some(index);
+        self.set_input(&self.messages[index].clone());
+        true
+    }
+
+    /// Show next message or the draft input after the newest one, return false if not navigating.
+    pub fn next_message(&mut self) -> bool {
+        match self.history_index {
+            None => false,
+            Some(index) if index + 1 < self.messages.len() => {
+                self.history_index = Some(index + 1);
+                self.set_input(&self.messages[index + 1].clone());
+                true
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.set_input(&self.draft.clone());
+                true
+            }
+        }
+    }
+
+    /// Search pattern and if any message matched on reverse searching history.
+    pub fn history_search(&self) -> Option<(&str, bool)> {
+        self.history_search
+            .as_ref()
+            .map(|search| (search.pattern.as_str(), search.index.is_some()))
+    }
+
+    fn start_history_search(&mut self) {
+        match &self.history_search {
+            // Ctrl+R again to find older matches
+            Some(search) => {
+                let end = search.index.unwrap_or(self.messages.len());
+                self.find_message(end);
+            }
+            None => {
+                if !self.browsing_history() {
+                    self.draft = self.input.clone();
+                }
+                self.history_search = Some(HistorySearch::default());
+            }
+        }
+    }
+
+    // Find newest message contains search pattern before `end` index
+    fn find_message(&mut self, end: usize) {
+        let Some(search) = &mut self.history_search else {
+            return;
+        };
+
+        let found = self.messages[..end.min(self.messages.len())]
+            .iter()
+            .rposition(|message| message.contains(&search.pattern));
+        if let Some(index) = found {
+            search.index = Some(index);
+            self.history_index = Some(index);
+            self.set_input(&self.messages[index].clone());
+        } else if end >= self.messages.len() {
+            // Keep previous match if only older matches not found
+            search.index = None;
+        }
+    }
+
+    fn handle_history_search(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            // Cancel searching and restore the input
+            KeyCode::Esc => {
+                self.cancel_history_search();
+            }
+            KeyCode::Char('g') | KeyCode::Char('G') if modifiers == KeyModifiers::CONTROL => {
+                self.cancel_history_search();
+            }
+            KeyCode::Char(c) => {
+                if let Some(search) = &mut self.history_search {
+                    search.pattern.push(c);
+                }
+                self.find_message(self.messages.len());
+            }
+            KeyCode::Backspace => {
+                if let Some(search) = &mut self.history_search {
+                    search.pattern.pop();
+                }
+                self.find_message(self.messages.len());
+            }
+            // Accept matched message and keep editing
+            _ => {
+                self.history_search = None;
+                self.handle_key(code, modifiers);
+            }
+        }
+    }
+
+    fn cancel_history_search(&mut self) {
+        self.history_search = None;
+        self.history_index = None;
+        self.set_input(&self.draft.clone());
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char('p') | KeyCode::Char('P') if modifiers == KeyModifiers::CONTROL => {
+                self.previous_message();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') if modifiers == KeyModifiers::CONTROL => {
+                self.next_message();
+            }
+            KeyCode::Tab if self.can_complete() => self.start_completion(),
+            KeyCode::Home => self.reset_cursor(),
+            KeyCode::Char('a') | KeyCode::Char('A') if modifiers == KeyModifiers::CONTROL => {
+                self.reset_cursor();
+            }
+            KeyCode::End => self.cursor_position = self.clamp_cursor(usize::MAX),
+            KeyCode::Char('e') | KeyCode::Char('E') if modifiers == KeyModifiers::CONTROL => {
+                self.cursor_position = self.clamp_cursor(usize::MAX);
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') if modifiers == KeyModifiers::CONTROL => {
+                self.delete_previous_word();
+            }
+            KeyCode::Char('u') | KeyCode::Char('U') if modifiers == KeyModifiers::CONTROL => {
+                self.delete_range(0, self.cursor_position);
+            }
+            KeyCode::Char('k') | KeyCode::Char('K') if modifiers == KeyModifiers::CONTROL => {
+                self.delete_range(self.cursor_position, usize::MAX);
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') if modifiers == KeyModifiers::ALT => {
+                self.move_word_left();
+            }
+            KeyCode::Char('f') | KeyCode::Char('F') if modifiers == KeyModifiers::ALT => {
+                self.move_word_right();
+            }
+            // Ignore other shortcuts, e.g. Ctrl+C
+            KeyCode::Char(_)
+                if modifiers == KeyModifiers::CONTROL || modifiers == KeyModifiers::ALT => {}
+            KeyCode::Char(to_insert) => {
+                self.enter_char(to_insert);
+            }
+            KeyCode::Backspace => {
+                self.delete_char();
+            }
+            KeyCode::Delete => {
+                self.delete_next_char();
+            }
+            KeyCode::Left => {
+                self.move_cursor_left();
+            }
+            KeyCode::Right => {
+                self.move_cursor_right();
+            }
+            _ => {}
+        }
     }
 }
 
@@ -107,22 +593,26 @@ impl Component for UserInput {
     }
 
     fn handle_events(&mut self, event: Event, state: &mut AppState) {
+        // Click to move the cursor, also closes completion popup and keeps found message
+        if let Event::Mouse(mouse_event) = event {
+            if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
+                self.completion = None;
+                self.history_search = None;
+                self.move_cursor_to(mouse_event.column.saturating_sub(self.area.x));
+            }
+        }
+
         if let Event::Key(key_event) = event {
-            match key_event.code {
-                KeyCode::Char(to_insert) => {
-                    self.enter_char(to_insert);
-                }
-                KeyCode::Backspace => {
-                    self.delete_char();
-                }
-                KeyCode::Left => {
-                    self.move_cursor_left();
-                }
-                KeyCode::Right => {
-                    self.move_cursor_right();
-                }
-                // TODO Handle more key events: Tab to insert space, Ctrl+W to delete previous word?
-                _ => {}
+            // Also closes completion popup, again to find older matches on searching history
+            if state.keymap.matches(Action::HistorySearch, &key_event) {
+                self.completion = None;
+                self.start_history_search();
+            } else if self.completing() {
+                self.handle_completion(key_event.code, key_event.modifiers);
+            } else if self.history_search.is_some() {
+                self.handle_history_search(key_event.code, key_event.modifiers);
+            } else {
+                self.handle_key(key_event.code, key_event.modifiers);
             }
         }
     }
@@ -137,6 +627,10 @@ impl Component for UserInput {
 
     fn set_focus(&mut self, focused: bool) {
         self.focused = focused;
+        // Close completion popup, e.g. on clicking other panels
+        if !focused {
+            self.completion = None;
+        }
     }
 
     fn set_hide(&mut self, hidden: bool) {
@@ -147,13 +641,34 @@ impl Component for UserInput {
         true
     }
 
-    fn help_keys(&self) -> Vec<String> {
-        // TODO Find a better way to map key events in fn handle_events to this?
-        vec![
-            format!("Search [{}]", KeySymbols::ENTER),
-            format!("Move cursor [{}{}]", KeySymbols::LEFT, KeySymbols::RIGHT),
-            format!("Delete Char [{}]", KeySymbols::BACKSPACE),
-            format!("Up/ Down [{}{}]", KeySymbols::UP, KeySymbols::DOWN),
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn set_area(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    fn help_keys(&self, keymap: &Keymap) -> Vec<String> {
+        // Editing keys are readline-style and not remappable
+        [
+            keymap.help("Search", &[Action::Search]),
+            Some(format!(
+                "Move cursor [{}{}]",
+                KeySymbols::LEFT,
+                KeySymbols::RIGHT
+            )),
+            Some(format!("Delete Char [{}]", KeySymbols::BACKSPACE)),
+            Some(format!("History [{}{}]", KeySymbols::UP, KeySymbols::DOWN)),
+            keymap.help("Search history", &[Action::HistorySearch]),
         ]
+        .into_iter()
+        .flatten()
+        .collect()
     }
 }
+
+// Letters and numbers of any script, e.g. Telefónica
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
@@ -0,0 +1,206 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::api::API_TIMEOUT;
+use crate::keymap::{Action, Keymap, Keys};
+use crate::theme::{LineStyle, Theme, NO_COLOR_ENV};
+use crate::util;
+
+/// Environment variable of the config file, overridden by `--config`.
+pub const CONFIG_ENV: &str = "STREND_CONFIG";
+// Settings can be overridden by environment variables, e.g. STREND_TICK_RATE=100
+const ENV_PREFIX: &str = "STREND_";
+
+/// Settings of config.toml, missing settings are defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Milliseconds between ticks, e.g. to check API responses
+    pub tick_rate: u64,
+    /// Seconds to wait for API responses
+    pub api_timeout: u64,
+    /// The oldest saved queries are removed after
+    pub max_saved_queries: usize,
+    /// Facet values checked on loading facets
+    pub selected_facet_lines: usize,
+    /// Facets of the search box on launch, e.g. country:10
+    pub facets: String,
+    /// Proxy of API requests, e.g. socks5://localhost:9050, proxy environment variables if empty
+    pub proxy: String,
+    /// Directory of exported files, e.g. ~/Downloads
+    pub export_dir: String,
+    /// Colors of the TUI and charts, e.g. colorblind, monochrome if NO_COLOR is set
+    pub theme: Theme,
+    /// Line colors in hex, e.g. ["#d50527", "#158940"], the default colors if empty
+    pub colors: Vec<String>,
+    /// Keys of actions, e.g. export = "ctrl+x", the default keys of missing actions
+    pub keys: BTreeMap<Action, Keys>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick_rate: 250,
+            api_timeout: API_TIMEOUT,
+            max_saved_queries: 5,
+            selected_facet_lines: 5,
+            facets: String::new(),
+            proxy: String::new(),
+            export_dir: ".".to_string(),
+            theme: Theme::default(),
+            colors: vec![],
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file, `config.toml` in the data directory if no `path`, and overrides
+    /// settings by environment variables. Missing default config file is the default settings.
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_owned(), true),
+            None => match util::get_data_dir() {
+                dir if dir.is_empty() => (String::new(), false),
+                dir => (format!("{}/config.toml", dir), false),
+            },
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound && !required => String::new(),
+            Err(err) => return Err(format!("Failed to read {} ({})", path, err)),
+        };
+
+        Config::parse(&text)
+            .map_err(|err| format!("Invalid {} ({})", path, err))?
+            .with_vars(std::env::vars())
+    }
+
+    /// Parses settings in TOML, errors have the line of the invalid setting.
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let config = toml::from_str::<Config>(text).map_err(|err| match err.span() {
+            Some(span) => format!(
+                "line {}, {}",
+                text[..span.start].lines().count().max(1),
+                err.message()
+            ),
+            None => err.message().to_owned(),
+        })?;
+        config.validate()?;
+        Ok(config.expand_home())
+    }
+
+    /// Overrides settings by environment variables, e.g. STREND_TICK_RATE=100
+    pub fn with_vars(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Config, String> {
+        let mut table = toml::Table::try_from(&self).map_err(|err| err.to_string())?;
+        let mut no_color = false;
+        for (name, value) in vars {
+            if name == NO_COLOR_ENV {
+                no_color = !value.is_empty();
+                continue;
+            }
+            let Some(key) = name
+                .strip_prefix(ENV_PREFIX)
+                .map(|key| key.to_lowercase())
+                .filter(|key| table.contains_key(key))
+            else {
+                continue;
+            };
+            // Strings as is, other values in TOML, e.g. 100 or ["#d50527"]
+            let value = match table[&key] {
+                toml::Value::String(_) => toml::Value::String(value),
+                _ => toml::from_str::<toml::Table>(&format!("value = {}", value))
+                    .ok()
+                    .and_then(|mut table| table.remove("value"))
+                    .unwrap_or(toml::Value::String(value)),
+            };
+            table.insert(key, value);
+
+            // Check each variable to show the invalid one
+            Config::deserialize(table.clone())
+                .map_err(|err| format!("Invalid {} ({})", name, err.message()))?
+                .validate()
+                .map_err(|err| format!("Invalid {} ({})", name, err))?;
+        }
+
+        let mut config = Config::deserialize(table).map_err(|err| err.message().to_owned())?;
+        if no_color {
+            config.theme = Theme::Monochrome;
+        }
+        Ok(config.expand_home())
+    }
+
+    /// Replaces the export directory, e.g. by --export-dir.
+    pub fn with_export_dir(mut self, dir: &str) -> Self {
+        self.export_dir = dir.to_owned();
+        self.expand_home()
+    }
+
+    // Expand home directory of the export directory, e.g. ~/Downloads
+    fn expand_home(mut self) -> Self {
+        if let (Some(dir), Some(home)) = (self.export_dir.strip_prefix("~/"), dirs::home_dir()) {
+            self.export_dir = format!("{}/{}", home.display(), dir);
+        }
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.tick_rate == 0 {
+            return Err("tick_rate must be greater than 0".to_string());
+        }
+        if self.api_timeout == 0 {
+            return Err("api_timeout must be greater than 0".to_string());
+        }
+        if self.max_saved_queries == 0 {
+            return Err("max_saved_queries must be greater than 0".to_string());
+        }
+        if let Some(color) = self
+            .colors
+            .iter()
+            .find(|color| parse_color(color).is_none())
+        {
+            return Err(format!(
+                "Invalid color {}, expected hex, e.g. #d50527",
+                color
+            ));
+        }
+        Keymap::default().with_keys(&self.keys)?;
+        Ok(())
+    }
+
+    /// Default keys replaced by keys of the config.
+    pub fn keymap(&self) -> Keymap {
+        Keymap::default().with_keys(&self.keys).unwrap_or_default()
+    }
+
+    /// Colors of chart lines, queries and facet values, colors of the theme if no `colors`.
+    pub fn line_colors(&self) -> Vec<Color> {
+        self.theme.line_colors(&self.parse_colors())
+    }
+
+    /// Colors and markers of chart lines, queries and facet values.
+    pub fn line_styles(&self) -> Vec<LineStyle> {
+        self.theme.line_styles(&self.parse_colors())
+    }
+
+    fn parse_colors(&self) -> Vec<Color> {
+        self.colors
+            .iter()
+            .filter_map(|color| parse_color(color))
+            .collect()
+    }
+}
+
+// Color from hex, e.g. #d50527
+fn parse_color(color: &str) -> Option<Color> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,193 @@
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::app::App;
+#[cfg(feature = "png")]
+use crate::image::png;
+use crate::output::escape_csv;
+use crate::report::html_report;
+use crate::util::timestamp;
+
+/// File formats to export chart data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Tsv,
+    Json,
+    Markdown,
+    /// Image of the displayed chart
+    Svg,
+    #[cfg(feature = "png")]
+    Png,
+    /// All saved queries in one HTML file
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: &'static [ExportFormat] = &[
+        ExportFormat::Csv,
+        ExportFormat::Tsv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+        ExportFormat::Svg,
+        #[cfg(feature = "png")]
+        ExportFormat::Png,
+        ExportFormat::Html,
+    ];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Svg => "svg",
+            #[cfg(feature = "png")]
+            ExportFormat::Png => "png",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    /// Next format in `ALL`, previous one if `back`.
+    pub fn next(self, back: bool) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|format| *format == self)
+            .unwrap_or(0);
+        let len = Self::ALL.len();
+        match back {
+            true => Self::ALL[(index + len - 1) % len],
+            false => Self::ALL[(index + 1) % len],
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Tsv => write!(f, "TSV"),
+            ExportFormat::Json => write!(f, "JSON"),
+            ExportFormat::Markdown => write!(f, "Markdown"),
+            ExportFormat::Svg => write!(f, "SVG"),
+            #[cfg(feature = "png")]
+            ExportFormat::Png => write!(f, "PNG"),
+            ExportFormat::Html => write!(f, "HTML report"),
+        }
+    }
+}
+
+/// Default file to export in the directory, e.g. ./strend-20230801-093000.csv
+pub fn default_path(dir: &str, format: ExportFormat, time: SystemTime) -> String {
+    format!(
+        "{}/strend-{}.{}",
+        dir.trim_end_matches('/'),
+        timestamp(time),
+        format.extension()
+    )
+}
+
+/// Formats chart data, first row is header and first column is month.
+///
+/// Images and reports aren't tables so they are empty, use `write_export` to render them.
+pub fn format_rows(rows: &[Vec<String>], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Svg | ExportFormat::Html => String::new(),
+        #[cfg(feature = "png")]
+        ExportFormat::Png => String::new(),
+        ExportFormat::Csv => join_rows(rows, ",", escape_csv),
+        ExportFormat::Tsv => join_rows(rows, "\t", |cell| cell.replace(['\t', '\n'], " ")),
+        // Months and values of each line, empty values are nulls, e.g. months without data
+        ExportFormat::Json => {
+            let Some((header, rows)) = rows.split_first() else {
+                return "{}\n".to_string();
+            };
+            let lines: Vec<Value> = header
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(column, label)| {
+                    let values: Vec<Value> = rows
+                        .iter()
+                        .map(|row| {
+                            let value = row.get(column).map_or("", |value| value.as_str());
+                            match (value.parse::<i64>(), value.parse::<f64>()) {
+                                (Ok(value), _) => json!(value),
+                                (_, Ok(value)) => json!(value),
+                                _ => Value::Null,
+                            }
+                        })
+                        .collect();
+                    json!({ "label": label, "values": values })
+                })
+                .collect();
+            let report = json!({
+                "months": rows.iter().map(|row| row[0].to_owned()).collect::<Vec<String>>(),
+                "lines": lines,
+            });
+            // Serialize plain values won't fail
+            serde_json::to_string_pretty(&report).unwrap_or_default() + "\n"
+        }
+        ExportFormat::Markdown => {
+            let mut rows: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.replace('|', "\\|")).collect())
+                .collect();
+            if rows.is_empty() {
+                return String::new();
+            }
+            let separator = rows[0].iter().map(|_| "---".to_string()).collect();
+            rows.insert(1, separator);
+            join_rows(&rows, " | ", |cell| cell.to_owned())
+                .lines()
+                .map(|line| format!("| {} |\n", line))
+                .collect()
+        }
+    }
+}
+
+fn join_rows(rows: &[Vec<String>], separator: &str, escape: impl Fn(&str) -> String) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| escape(cell))
+                .collect::<Vec<String>>()
+                .join(separator)
+                + "\n"
+        })
+        .collect()
+}
+
+/// Write chart data, the displayed chart image or the report of saved queries to the file, fails
+/// with `AlreadyExists` if the file exists and not `overwrite`.
+pub fn write_export(
+    app: &App,
+    path: &str,
+    format: ExportFormat,
+    overwrite: bool,
+) -> io::Result<()> {
+    let chart = &app.line_chart;
+    let contents = match format {
+        ExportFormat::Html => html_report(app, SystemTime::now()).into_bytes(),
+        ExportFormat::Svg => chart.image.svg().into_bytes(),
+        #[cfg(feature = "png")]
+        ExportFormat::Png => png(&chart.image.svg())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        // Derived series are exported next to the counts
+        _ => format_rows(&chart.export_data(), format).into_bytes(),
+    };
+
+    let mut file = match overwrite {
+        true => OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?,
+        false => OpenOptions::new().write(true).create_new(true).open(path)?,
+    };
+    file.write_all(&contents)
+}
@@ -1,16 +1,41 @@
-use std::{fs::File, io::Write};
+use std::io::ErrorKind;
 
 use crate::{
     app::{App, AppResult, AppState},
-    components::Component,
+    components::{user_input::UserInput, Component},
+    export::write_export,
+    keymap::{Action, Context, Keymap},
 };
-use crossterm::event::{Event, KeyCode, KeyModifiers};
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEvent, MouseEventKind};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_events(event: Event, app: &mut App, state: &mut AppState) -> AppResult<()> {
     // Use to prevent loop MultiStatefulList.state.select(Some(index))
     state.submitted = false;
 
+    // Export dialog takes all keys until closed
+    if !app.export_dialog.hidden() {
+        if let Event::Key(key_event) = event {
+            if state.keymap.matches(Action::Quit, &key_event) {
+                app.quit();
+            } else if state.keymap.matches(Action::Unfocus, &key_event) {
+                app.export_dialog.close();
+            } else if state.keymap.action(Context::Dialog, &key_event) == Some(Action::Confirm) {
+                export_chart(app, state);
+            } else {
+                app.export_dialog.handle_events(event, state);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Event::Mouse(mouse_event) = event {
+        handle_mouse_events(mouse_event, app, state);
+        return Ok(());
+    }
+    // Completion popup in searchbox handles Tab, Enter and Esc keys itself, history search Esc
+    let mut captured = false;
+
     // On unfocused any panels
     if !state.focused {
         // match event {
@@ -30,6 +55,7 @@ pub fn handle_events(event: Event, app: &mut App, state: &mut AppState) -> AppRe
     } else {
         // Let each widget handle events
         let widget_index = app.widget_index;
+        captured = searchbox_captures(&event, app, &state.keymap);
         let mut widgets = app.get_widgets();
 
         // Only focused & visible widget can handle events
@@ -39,33 +65,36 @@ pub fn handle_events(event: Event, app: &mut App, state: &mut AppState) -> AppRe
             // Unfocus current widget
 
             if let Event::Key(key_event) = event {
-                if key_event.code == KeyCode::Esc {
+                if state.keymap.matches(Action::Unfocus, &key_event) && !captured {
                     state.focused = false;
                     widgets[widget_index].set_focus(false);
                 }
             }
 
             // Special handler for focused searchbox
-            if widgets[widget_index].allow_enter() {
+            if widgets[widget_index].allow_enter() && !captured {
                 if let Event::Key(key_event) = event {
                     match key_event.code {
-                        // Switch between searchbox lines
+                        _ if state.keymap.matches(Action::Search, &key_event) => {
+                            state.submitted = true;
+                            app.search(state.sender.clone())?;
+                        }
+                        // Navigate history, switch between searchbox lines if no more messages
                         KeyCode::Up => {
-                            if !app.search_input.focused() {
+                            let browsed = focused_input(app).previous_message();
+                            if !browsed && !app.search_input.focused() {
                                 let index = app.get_widget_index(app.search_input.id());
                                 app.select_widget(index);
                             }
                         }
                         KeyCode::Down => {
-                            if !app.facets_input.focused() {
+                            // Months range is on the same line with facets
+                            let browsed = focused_input(app).next_message();
+                            if !browsed && app.search_input.focused() {
                                 let index = app.get_widget_index(app.facets_input.id());
                                 app.select_widget(index);
                             }
                         }
-                        KeyCode::Enter => {
-                            state.submitted = true;
-                            app.search(state.sender.clone())?;
-                        }
                         _ => {}
                     }
                 }
@@ -74,55 +103,110 @@ pub fn handle_events(event: Event, app: &mut App, state: &mut AppState) -> AppRe
     }
 
     if let Event::Key(key_event) = event {
-        match key_event.code {
-            KeyCode::Tab => {
-                app.switch_widgets(state, false)?;
-            }
-            KeyCode::BackTab => {
-                app.switch_widgets(state, true)?;
-            }
-            // Export selected chart data to CSV file
-            KeyCode::Char('e') | KeyCode::Char('E') => {
-                if key_event.modifiers == KeyModifiers::CONTROL {
-                    if app.line_chart.data.is_empty() || app.line_chart.data[0].len() == 1 {
-                        state.app_log = "No chart data to export".to_string();
-                    } else {
-                        let mut has_error = false;
-                        let outfile = "./data.csv";
-
-                        match File::create(outfile) {
-                            Ok(mut file) => {
-                                state.app_log = format!("Exported chart to {}", outfile);
-                                for row in &app.line_chart.data {
-                                    let line = row.join(",") + "\n";
-                                    if file.write_all(line.as_bytes()).is_err() {
-                                        has_error = true;
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                has_error = true;
-                            }
-                        };
-
-                        if has_error {
-                            state.app_log = "Failed to export chart data".to_string();
-                        }
-                    }
-
-                    // Reset ticks
-                    app.ticks = 0;
-                }
+        let keymap = &state.keymap;
+        if keymap.matches(Action::SwitchPanel, &key_event) && !captured {
+            app.switch_widgets(state, false)?;
+        } else if keymap.matches(Action::SwitchPanelBack, &key_event) && !captured {
+            app.switch_widgets(state, true)?;
+        } else if keymap.matches(Action::Export, &key_event)
+            // Export selected chart data, Ctrl+E moves cursor to end in searchbox
+            && !app.search_input.focused()
+            && !app.facets_input.focused()
+            && !app.range_input.focused()
+        {
+            if app.line_chart.data.is_empty() || app.line_chart.data[0].len() == 1 {
+                state.app_log = "No chart data to export".to_string();
+                // Reset ticks
+                app.ticks = 0;
+            } else {
+                app.export_dialog.open(&app.config.export_dir);
             }
+        } else if keymap.matches(Action::Quit, &key_event) {
             // Exit application on `Ctrl-C`
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                if key_event.modifiers == KeyModifiers::CONTROL {
-                    app.quit();
-                }
-            }
-            _ => {}
+            app.quit();
         }
     }
 
     Ok(())
 }
+
+// Export chart data to the file of the dialog, ask to overwrite if the file exists
+fn export_chart(app: &mut App, state: &mut AppState) {
+    let path = app.export_dialog.path.get_input().trim().to_owned();
+    if path.is_empty() {
+        return;
+    }
+
+    match write_export(
+        app,
+        &path,
+        app.export_dialog.format,
+        app.export_dialog.overwrite,
+    ) {
+        Ok(_) => {
+            state.app_log = format!("Exported chart to {}", path);
+            app.export_dialog.close();
+        }
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            app.export_dialog.overwrite = true;
+        }
+        Err(err) => {
+            state.app_log = format!("Failed to export chart data ({})", err);
+            app.export_dialog.close();
+        }
+    }
+
+    // Reset ticks
+    app.ticks = 0;
+}
+
+fn focused_input(app: &mut App) -> &mut UserInput {
+    if app.search_input.focused() {
+        &mut app.search_input
+    } else if app.facets_input.focused() {
+        &mut app.facets_input
+    } else {
+        &mut app.range_input
+    }
+}
+
+// Click focuses the panel under the mouse, the panel handles clicks, scrolls and hovering itself
+fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App, state: &mut AppState) {
+    let (column, row) = (mouse_event.column, mouse_event.row);
+    let widget_index = app.get_widgets().iter().position(|widget| {
+        let area = widget.area();
+        !widget.hidden()
+            && (area.left()..area.right()).contains(&column)
+            && (area.top()..area.bottom()).contains(&row)
+    });
+
+    match (widget_index, mouse_event.kind) {
+        (Some(index), MouseEventKind::Down(MouseButton::Left)) => {
+            app.select_widget(index);
+            state.focused = true;
+            app.get_widgets()[index].handle_events(Event::Mouse(mouse_event), state);
+        }
+        (
+            Some(index),
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown | MouseEventKind::Moved,
+        ) => {
+            app.get_widgets()[index].handle_events(Event::Mouse(mouse_event), state);
+        }
+        // Hide chart values on leaving the chart, keep them if focused the chart
+        (None, MouseEventKind::Moved) if !app.line_chart.focused() => {
+            app.line_chart.cursor = None;
+        }
+        _ => {}
+    }
+}
+
+// If focused searchbox is showing completion popup or completes the name with Tab key, or
+// searching history cancels the search with Esc
+fn searchbox_captures(event: &Event, app: &App, keymap: &Keymap) -> bool {
+    [&app.search_input, &app.facets_input].iter().any(|input| {
+        input.focused()
+            && (input.completing()
+                || matches!(event, Event::Key(key_event) if key_event.code == KeyCode::Tab && input.can_complete())
+                || matches!(event, Event::Key(key_event) if input.history_search().is_some() && keymap.matches(Action::Unfocus, key_event)))
+    })
+}
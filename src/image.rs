@@ -0,0 +1,288 @@
+use ratatui::style::Color;
+
+const WIDTH: f64 = 1200.0;
+const PLOT_HEIGHT: f64 = 400.0;
+// Space of the title on top, Y axis ticks on the left and X axis ticks below the plot
+const TOP: f64 = 60.0;
+const LEFT: f64 = 90.0;
+const RIGHT: f64 = 40.0;
+const BOTTOM: f64 = 50.0;
+const LEGEND_ROW: f64 = 22.0;
+// Common fonts first as generic families are mapped to Arial on rendering PNG
+const FONT: &str = "font-family=\"Helvetica, Arial, DejaVu Sans, sans-serif\"";
+// Dash patterns of lines without colors, the first one is solid
+const DASHES: [&str; 4] = ["", "8 4", "2 4", "8 4 2 4"];
+
+/// Chart as displayed on the TUI, the visible months of the lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartImage {
+    pub title: String,
+    /// Y axis title, e.g. 3-month average, log
+    pub y_title: String,
+    pub x_bounds: [f64; 2],
+    pub x_ticks: Vec<String>,
+    /// Month labels of X values, show counts of the points on hovering if any
+    pub x_labels: Vec<String>,
+    pub y_bounds: [f64; 2],
+    pub y_ticks: Vec<String>,
+    /// Fill the area between each line and the previous one, e.g. stacked shares
+    pub stacked: bool,
+    /// Tell lines apart by dashes or shades instead of colors, e.g. monochrome theme
+    pub dashed: bool,
+    pub lines: Vec<ImageLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageLine {
+    pub label: String,
+    pub color: Color,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl ChartImage {
+    /// Renders the chart to SVG, the lines are listed below the plot.
+    pub fn svg(&self) -> String {
+        let height = TOP + PLOT_HEIGHT + BOTTOM + LEGEND_ROW * self.lines.len() as f64;
+        let (right, bottom) = (WIDTH - RIGHT, TOP + PLOT_HEIGHT);
+        let mut svg = vec![
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+                w = WIDTH,
+                h = height
+            ),
+            format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>", WIDTH, height),
+            format!(
+                "<text x=\"{}\" y=\"30\" {} font-size=\"18\" font-weight=\"bold\">{}</text>",
+                LEFT,
+                FONT,
+                escape_xml(&self.title)
+            ),
+        ];
+
+        if !self.y_title.is_empty() {
+            svg.push(format!(
+                "<text x=\"{x}\" y=\"{y}\" {} font-size=\"13\" fill=\"gray\" text-anchor=\"middle\" transform=\"rotate(-90 {x} {y})\">{}</text>",
+                FONT,
+                escape_xml(&self.y_title),
+                x = 20,
+                y = TOP + PLOT_HEIGHT / 2.0
+            ));
+        }
+
+        // Ticks are spread evenly along the axes like the TUI chart, with grid lines of Y ticks
+        for (index, tick) in self.y_ticks.iter().enumerate() {
+            let y = bottom - spread(index, self.y_ticks.len()) * PLOT_HEIGHT;
+            svg.push(format!(
+                "<line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#e5e5e5\"/>",
+                LEFT,
+                right,
+                y = y
+            ));
+            svg.push(format!(
+                "<text x=\"{}\" y=\"{:.1}\" {} font-size=\"13\" fill=\"gray\" text-anchor=\"end\">{}</text>",
+                LEFT - 10.0,
+                y + 4.0,
+                FONT,
+                escape_xml(tick)
+            ));
+        }
+        for (index, tick) in self.x_ticks.iter().enumerate() {
+            let ratio = spread(index, self.x_ticks.len());
+            let anchor = match index {
+                0 => "start",
+                index if index + 1 == self.x_ticks.len() => "end",
+                _ => "middle",
+            };
+            svg.push(format!(
+                "<text x=\"{:.1}\" y=\"{}\" {} font-size=\"13\" fill=\"gray\" text-anchor=\"{}\">{}</text>",
+                LEFT + ratio * (right - LEFT),
+                bottom + 22.0,
+                FONT,
+                anchor,
+                escape_xml(tick)
+            ));
+        }
+        svg.push(format!(
+            "<path d=\"M{l} {t} V{b} H{r}\" fill=\"none\" stroke=\"gray\"/>",
+            l = LEFT,
+            t = TOP,
+            b = bottom,
+            r = right
+        ));
+
+        let mut previous: Option<&ImageLine> = None;
+        for (index, line) in self.lines.iter().enumerate() {
+            let color = hex(line.color);
+            let points: Vec<String> = line.points.iter().map(|point| self.point(*point)).collect();
+            svg.push("<g class=\"line\">".to_string());
+            match self.stacked {
+                // Back along the previous line or the X axis
+                true => {
+                    let base: Vec<String> = match previous {
+                        Some(previous) => previous
+                            .points
+                            .iter()
+                            .rev()
+                            .map(|point| self.point(*point))
+                            .collect(),
+                        None => line
+                            .points
+                            .iter()
+                            .rev()
+                            .map(|point| self.point((point.0, self.y_bounds[0])))
+                            .collect(),
+                    };
+                    svg.push(format!(
+                        "<polygon points=\"{} {}\" fill=\"{}\" fill-opacity=\"{:.2}\"/>",
+                        points.join(" "),
+                        base.join(" "),
+                        color,
+                        self.opacity(index)
+                    ));
+                }
+                false => svg.push(format!(
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" stroke-linejoin=\"round\"{}/>",
+                    points.join(" "),
+                    color,
+                    self.dash(index)
+                )),
+            }
+            for (x, y) in line.points.iter().filter(|_| !self.stacked) {
+                let Some(month) = self.x_labels.get(*x as usize) else {
+                    continue;
+                };
+                let (cx, cy) = self.position((*x, *y));
+                svg.push(format!(
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" fill=\"{}\"><title>{}: {}</title></circle>",
+                    cx,
+                    cy,
+                    color,
+                    escape_xml(month),
+                    y
+                ));
+            }
+            svg.push("</g>".to_string());
+            previous = Some(line);
+        }
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let y = bottom + BOTTOM + LEGEND_ROW * index as f64;
+            svg.push(match self.dashed && !self.stacked {
+                // Sample of the dashes
+                true => format!(
+                    "<g class=\"legend\"><line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"{}\" stroke-width=\"2\"{}/>",
+                    LEFT - 4.0,
+                    LEFT + 14.0,
+                    hex(line.color),
+                    self.dash(index),
+                    y = y - 5.0
+                ),
+                false => format!(
+                    "<g class=\"legend\"><rect x=\"{}\" y=\"{}\" width=\"12\" height=\"12\" fill=\"{}\"{}/>",
+                    LEFT,
+                    y - 11.0,
+                    hex(line.color),
+                    match self.dashed {
+                        true => format!(" fill-opacity=\"{:.2}\"", self.opacity(index)),
+                        false => String::new(),
+                    }
+                ),
+            });
+            svg.push(format!(
+                "<text x=\"{}\" y=\"{}\" {} font-size=\"13\">{}</text></g>",
+                LEFT + 20.0,
+                y,
+                FONT,
+                escape_xml(&line.label)
+            ));
+        }
+
+        svg.push("</svg>".to_string());
+        svg.join("\n") + "\n"
+    }
+
+    // Dash pattern attribute of the nth line if dashed
+    fn dash(&self, index: usize) -> String {
+        match DASHES[index % DASHES.len()] {
+            dashes if self.dashed && !dashes.is_empty() => {
+                format!(" stroke-dasharray=\"{}\"", dashes)
+            }
+            _ => String::new(),
+        }
+    }
+
+    // Opacity of the nth stacked area, lighter shades of the same color if dashed
+    fn opacity(&self, index: usize) -> f64 {
+        match self.dashed {
+            true => 0.85 - 0.2 * (index % DASHES.len()) as f64,
+            false => 0.85,
+        }
+    }
+
+    // Pixel position of the point in the plot
+    fn position(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let ratio = |value: f64, [min, max]: [f64; 2]| match max > min {
+            true => ((value - min) / (max - min)).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        (
+            LEFT + ratio(x, self.x_bounds) * (WIDTH - RIGHT - LEFT),
+            TOP + PLOT_HEIGHT - ratio(y, self.y_bounds) * PLOT_HEIGHT,
+        )
+    }
+
+    // Point of polylines and polygons, e.g. 90.0,460.0
+    fn point(&self, point: (f64, f64)) -> String {
+        let (x, y) = self.position(point);
+        format!("{:.1},{:.1}", x, y)
+    }
+}
+
+/// Renders the SVG to PNG, texts are drawn with system fonts.
+#[cfg(feature = "png")]
+pub fn png(svg: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+    use usvg::{fontdb, TreeParsing, TreePostProc};
+
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    let mut tree =
+        usvg::Tree::from_str(svg, &usvg::Options::default()).map_err(|err| err.to_string())?;
+    tree.postprocess(
+        usvg::PostProcessingSteps {
+            convert_text_into_paths: true,
+        },
+        &fonts,
+    );
+
+    let size = tree.size.to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| "Invalid image size".to_string())?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|err| err.to_string())
+}
+
+// Position of the nth tick from 0 to 1
+fn spread(index: usize, len: usize) -> f64 {
+    match len > 1 {
+        true => index as f64 / (len - 1) as f64,
+        false => 0.0,
+    }
+}
+
+fn hex(color: Color) -> String {
+    match color {
+        Color::Rgb(red, green, blue) => format!("#{:02x}{:02x}{:02x}", red, green, blue),
+        // Default color of the terminal, e.g. monochrome theme
+        Color::Reset => "black".to_string(),
+        _ => "gray".to_string(),
+    }
+}
+
+/// Escape texts in SVG and HTML.
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
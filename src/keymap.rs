@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::components::KeySymbols;
+
+/// Where the action is handled, actions of different panels may share keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// Any panel, also the export dialog for `Quit` and `Unfocus`
+    Global,
+    /// Export dialog, its file is edited like the search box
+    Dialog,
+    /// Search box, facets and months inputs
+    Search,
+    /// Saved queries and facet values
+    List,
+    Chart,
+}
+
+impl Context {
+    // Keys the panel handles itself, they are not remappable, e.g. arrows moving the cursor
+    fn fixed_keys(&self) -> &'static [&'static str] {
+        match self {
+            Context::Global => &[],
+            // Formats, the dialog handles keys after quit and unfocus
+            Context::Dialog => &["up", "down", "tab", "backtab"],
+            Context::Search => &[
+                "up",
+                "down",
+                "left",
+                "right",
+                "home",
+                "end",
+                "backspace",
+                "delete",
+                "ctrl+a",
+                "ctrl+e",
+                "ctrl+w",
+                "ctrl+u",
+                "ctrl+k",
+                "ctrl+p",
+                "ctrl+n",
+                "ctrl+g",
+                "alt+b",
+                "alt+f",
+            ],
+            Context::List => &["up", "down"],
+            Context::Chart => &["left", "right", "shift+left", "shift+right", "home", "end"],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Context::Global => "all panels",
+            Context::Dialog => "the export dialog",
+            Context::Search => "the search box",
+            Context::List => "lists",
+            Context::Chart => "the chart",
+        }
+    }
+}
+
+/// Remappable actions, named in snake case in `[keys]` of config.toml, e.g. `zoom_in`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    SwitchPanel,
+    SwitchPanelBack,
+    Export,
+    Quit,
+    Unfocus,
+    Search,
+    HistorySearch,
+    Toggle,
+    SelectAll,
+    UnselectAll,
+    NextFacet,
+    PreviousFacet,
+    Share,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    ZoomRange,
+    YScale,
+    Change,
+    Stats,
+    FewerMonths,
+    MoreMonths,
+    Confirm,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::SwitchPanel,
+        Action::SwitchPanelBack,
+        Action::Export,
+        Action::Quit,
+        Action::Unfocus,
+        Action::Search,
+        Action::HistorySearch,
+        Action::Toggle,
+        Action::SelectAll,
+        Action::UnselectAll,
+        Action::NextFacet,
+        Action::PreviousFacet,
+        Action::Share,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ResetZoom,
+        Action::ZoomRange,
+        Action::YScale,
+        Action::Change,
+        Action::Stats,
+        Action::FewerMonths,
+        Action::MoreMonths,
+        Action::Confirm,
+    ];
+
+    pub fn context(&self) -> Context {
+        match self {
+            Action::SwitchPanel
+            | Action::SwitchPanelBack
+            | Action::Export
+            | Action::Quit
+            | Action::Unfocus => Context::Global,
+            Action::Search | Action::HistorySearch => Context::Search,
+            Action::Toggle
+            | Action::SelectAll
+            | Action::UnselectAll
+            | Action::NextFacet
+            | Action::PreviousFacet
+            | Action::Share => Context::List,
+            Action::Confirm => Context::Dialog,
+            _ => Context::Chart,
+        }
+    }
+
+    // Name in config.toml, e.g. zoom_in
+    fn name(&self) -> String {
+        toml::Value::try_from(self)
+            .ok()
+            .and_then(|value| value.as_str().map(|name| name.to_owned()))
+            .unwrap_or_else(|| format!("{:?}", self))
+    }
+
+    // Keys of the action without [keys] in config.toml
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::SwitchPanel => &["tab"],
+            Action::SwitchPanelBack => &["backtab"],
+            Action::Export => &["ctrl+e"],
+            Action::Quit => &["ctrl+c"],
+            Action::Unfocus => &["esc"],
+            Action::Search => &["enter"],
+            Action::HistorySearch => &["ctrl+r"],
+            Action::Toggle => &["enter"],
+            Action::SelectAll => &["right"],
+            Action::UnselectAll => &["left"],
+            Action::NextFacet => &["]"],
+            Action::PreviousFacet => &["["],
+            Action::Share => &["%"],
+            Action::ZoomIn => &["+", "="],
+            Action::ZoomOut => &["-"],
+            Action::ResetZoom => &["0"],
+            Action::ZoomRange => &["enter"],
+            Action::YScale => &["s"],
+            Action::Change => &["d"],
+            Action::Stats => &["i"],
+            Action::FewerMonths => &["<"],
+            Action::MoreMonths => &[">"],
+            Action::Confirm => &["enter"],
+        }
+    }
+}
+
+/// Keys of an action in config.toml, e.g. `export = "ctrl+x"` or `zoom_in = ["+", "="]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn names(&self) -> &[String] {
+        match self {
+            Keys::One(name) => std::slice::from_ref(name),
+            Keys::Many(names) => names,
+        }
+    }
+}
+
+/// Key with modifiers, e.g. ctrl+e
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Parses names of keys joined by `+`, e.g. `ctrl+e`, `shift+left`, `f2` or `+`.
+    pub fn parse(text: &str) -> Result<KeyChord, String> {
+        let invalid = || format!("Invalid key {:?}", text);
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => match text.rsplit_once('+') {
+                Some((modifiers, key)) if !key.is_empty() => (modifiers, key),
+                _ => ("", text),
+            },
+        };
+
+        let mut chord = KeyChord {
+            code: KeyCode::Null,
+            modifiers: KeyModifiers::NONE,
+        };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            chord.modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        chord.code = match key.to_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            name => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (Some('f'), Some(_)) => match name[1..].parse() {
+                        Ok(number @ 1..=12) => KeyCode::F(number),
+                        _ => return Err(invalid()),
+                    },
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Ok(chord)
+    }
+
+    /// If the key event is this key, letters and symbols may be typed with Shift, e.g. `S` or `%`.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let mut modifiers = event.modifiers;
+        if let KeyCode::Char(_) | KeyCode::BackTab = event.code {
+            if !self.modifiers.contains(KeyModifiers::SHIFT) {
+                modifiers.remove(KeyModifiers::SHIFT);
+            }
+        }
+        let code = match event.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        code == self.code && modifiers == self.modifiers
+    }
+
+    // If the key types a character in inputs, e.g. q or shift+q
+    fn is_printable(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    /// Symbols of the key shown in the help bar, e.g. ^E or ⇧←
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (modifier, symbol) in [
+            (KeyModifiers::CONTROL, KeySymbols::CONTROL),
+            (KeyModifiers::ALT, KeySymbols::ALT),
+            (KeyModifiers::SHIFT, KeySymbols::SHIFT),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(&symbol.to_string());
+            }
+        }
+
+        let symbol = match self.code {
+            KeyCode::Enter => KeySymbols::ENTER,
+            KeyCode::Tab => KeySymbols::TAB,
+            KeyCode::BackTab => KeySymbols::BACK_TAB,
+            KeyCode::Esc => KeySymbols::ESC,
+            KeyCode::Backspace => KeySymbols::BACKSPACE,
+            KeyCode::Delete => KeySymbols::DELETE,
+            KeyCode::Insert => KeySymbols::INSERT,
+            KeyCode::Home => KeySymbols::HOME,
+            KeyCode::End => KeySymbols::END,
+            KeyCode::PageUp => KeySymbols::PAGE_UP,
+            KeyCode::PageDown => KeySymbols::PAGE_DOWN,
+            KeyCode::Up => KeySymbols::UP,
+            KeyCode::Down => KeySymbols::DOWN,
+            KeyCode::Left => KeySymbols::LEFT,
+            KeyCode::Right => KeySymbols::RIGHT,
+            KeyCode::Char(' ') => return label + "Space",
+            KeyCode::Char(c) if self.modifiers.is_empty() => return label + &c.to_string(),
+            // Control keys are shown in upper case, e.g. ^C
+            KeyCode::Char(c) => return label + &c.to_ascii_uppercase().to_string(),
+            code => return label + &format!("{:?}", code),
+        };
+        label + &symbol.to_string()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    /// Name of the key in config.toml, e.g. ctrl+e
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// Keys of each action, used to handle key events and to show them in the help bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    keys: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = Action::ALL
+            .iter()
+            .map(|action| {
+                let chords = action
+                    .default_keys()
+                    .iter()
+                    .filter_map(|name| KeyChord::parse(name).ok())
+                    .collect();
+                (*action, chords)
+            })
+            .collect();
+        Self { keys }
+    }
+}
+
+impl Keymap {
+    /// Replaces keys of the actions, a key can't be bound to two actions of the same panel or to
+    /// a global action and another action. Global and search box actions need keys that aren't
+    /// typed in inputs, e.g. ctrl+q instead of q.
+    pub fn with_keys(mut self, keys: &BTreeMap<Action, Keys>) -> Result<Keymap, String> {
+        for (action, names) in keys {
+            let chords = names
+                .names()
+                .iter()
+                .map(|name| KeyChord::parse(name))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("{} of {}", err, action.name()))?;
+            if let Context::Global | Context::Search | Context::Dialog = action.context() {
+                if let Some(chord) = chords.iter().find(|chord| chord.is_printable()) {
+                    return Err(format!(
+                        "Key {} of {} is typed in inputs, use it with ctrl or alt",
+                        chord,
+                        action.name()
+                    ));
+                }
+            }
+            self.keys.insert(*action, chords);
+        }
+
+        // Fixed keys of the panel and of all panels for global actions, export is ignored in the
+        // search box where ctrl+e moves the cursor. Files of the export dialog are edited like the
+        // search box.
+        for action in Action::ALL {
+            let contexts = [
+                Context::Dialog,
+                Context::Search,
+                Context::List,
+                Context::Chart,
+            ];
+            let fixed = contexts
+                .into_iter()
+                .filter(|context| match (action.context(), *context) {
+                    (Context::Global, Context::Dialog) => {
+                        matches!(action, Action::Quit | Action::Unfocus)
+                    }
+                    (Context::Global, Context::Search) => *action != Action::Export,
+                    (Context::Global, _) => true,
+                    (Context::Dialog, Context::Search) => true,
+                    (own, context) => own == context,
+                })
+                .flat_map(|context| {
+                    context
+                        .fixed_keys()
+                        .iter()
+                        .map(move |key| (*key, context.name()))
+                });
+            for (key, name) in fixed {
+                let chord = KeyChord::parse(key)?;
+                if self.chords(*action).contains(&chord) {
+                    return Err(format!(
+                        "Key {} of {} is used by {}",
+                        chord,
+                        action.name(),
+                        name
+                    ));
+                }
+            }
+        }
+
+        for (index, action) in Action::ALL.iter().enumerate() {
+            for other in &Action::ALL[index + 1..] {
+                if action.context() != other.context()
+                    && action.context() != Context::Global
+                    && other.context() != Context::Global
+                {
+                    continue;
+                }
+                if let Some(chord) = self
+                    .chords(*action)
+                    .iter()
+                    .find(|chord| self.chords(*other).contains(chord))
+                {
+                    return Err(format!(
+                        "Key {} is bound to both {} and {}",
+                        chord,
+                        action.name(),
+                        other.name()
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.keys.get(&action).map_or(&[], |chords| chords)
+    }
+
+    /// If the key event is one of the keys of the action.
+    pub fn matches(&self, action: Action, event: &KeyEvent) -> bool {
+        self.chords(action).iter().any(|chord| chord.matches(event))
+    }
+
+    /// Action of the panel bound to the key event, if any.
+    pub fn action(&self, context: Context, event: &KeyEvent) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .find(|action| action.context() == context && self.matches(**action, event))
+            .copied()
+    }
+
+    /// Help of the actions with their first keys, e.g. Zoom [+-0], none if the actions are unbound.
+    pub fn help(&self, name: &str, actions: &[Action]) -> Option<String> {
+        let labels: String = actions
+            .iter()
+            .filter_map(|action| self.chords(*action).first())
+            .map(|chord| chord.label())
+            .collect();
+        match labels.is_empty() {
+            true => None,
+            false => Some(format!("{} [{}]", name, labels)),
+        }
+    }
+}
@@ -1,21 +1,66 @@
 /// Application.
 pub mod app;
 
+/// Settings of config.toml.
+pub mod config;
+
+/// Shodan Trends API client.
+pub mod api;
+
+/// On-disk cache of API responses.
+pub mod cache;
+
+/// Shodan query tokenizer and validation.
+pub mod query;
+
+/// Completion of search filter and facet names.
+pub mod completion;
+
+/// Months range of search results.
+pub mod range;
+
+/// Summary statistics of charts.
+pub mod stats;
+
+/// Saved sessions of queries.
+pub mod session;
+
 /// Terminal events handler.
 pub mod event;
 
 /// Widget renderer.
 pub mod ui;
 
+/// Color themes of the TUI and charts.
+pub mod theme;
+
 /// Terminal user interface.
 pub mod tui;
 
 /// Event handler.
 pub mod handler;
 
+/// Remappable keys of actions.
+pub mod keymap;
+
 /// Utilities.
 pub mod util;
 
+/// Headless output formatters.
+pub mod output;
+
+/// Export chart data to files.
+pub mod export;
+
+/// Render charts to SVG and PNG images.
+pub mod image;
+
+/// HTML report of saved queries.
+pub mod report;
+
+/// Render charts to text without the TUI.
+pub mod plot;
+
 /// Defined components.
 pub mod components;
 
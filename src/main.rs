@@ -1,15 +1,30 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use crossterm::event::Event as CrosstermEvent;
+use crossterm::terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::collections::HashMap;
-use std::io;
+use std::env;
+use std::io::{self, IsTerminal};
 use std::sync::mpsc;
-use strend::app::{App, AppResult, AppState, EXIT_ERROR_CODE, EXIT_SUCCESS_CODE};
+use std::time::Duration;
+use strend::api::{ApiError, SearchResponse, TrendsClient};
+use strend::app::{
+    App, AppResult, AppState, Chart, EXIT_API_ERROR_CODE, EXIT_ERROR_CODE, EXIT_NETWORK_ERROR_CODE,
+    EXIT_NO_RESULTS_CODE, EXIT_SUCCESS_CODE,
+};
+use strend::cache::{CacheMode, ResponseCache};
+use strend::components::line_chart::ROLLING_MONTHS;
+use strend::config::{Config, CONFIG_ENV};
 use strend::event::{Event, EventHandler};
 use strend::handler::handle_events;
+use strend::output::{format_search, OutputFormat};
+use strend::plot::{self, PlotMarker};
+use strend::range::{parse_month, DateRange};
+use strend::session::SessionStore;
+use strend::theme::Theme;
 use strend::tui::Tui;
-use strend::util::init_api_key;
+use strend::util::{get_api_key, init_api_key};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -26,29 +41,349 @@ struct Cli {
     #[arg(long)]
     facets: Option<String>,
 
+    /// Restore saved queries of the session on launch and save them on exit
+    #[arg(long)]
+    session: Option<String>,
+
+    /// Don't load or save cached API responses
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Ignore cached API responses and save new ones
+    #[arg(long, global = true, conflicts_with = "no_cache")]
+    refresh: bool,
+
+    /// Only keep results since the month, e.g. 2022-01
+    #[arg(long, global = true, value_parser = parse_month)]
+    since: Option<String>,
+
+    /// Only keep results until the month, e.g. 2023-06
+    #[arg(long, global = true, value_parser = parse_month)]
+    until: Option<String>,
+
+    /// Months of the rolling average on the chart
+    #[arg(
+        long,
+        value_name = "MONTHS",
+        default_value_t = ROLLING_MONTHS as u8,
+        value_parser = clap::value_parser!(u8).range(2..=24)
+    )]
+    rolling: u8,
+
+    /// Config file, default to config.toml in the config directory or STREND_CONFIG
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<String>,
+
+    /// Milliseconds between ticks, overrides tick_rate of the config
+    #[arg(long, global = true, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    tick_rate: Option<u64>,
+
+    /// Seconds to wait for API responses, overrides api_timeout of the config
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    api_timeout: Option<u64>,
+
+    /// Proxy of API requests, e.g. socks5://localhost:9050, overrides proxy of the config
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Directory of exported files, overrides export_dir of the config
+    #[arg(long, global = true, value_name = "DIR")]
+    export_dir: Option<String>,
+
+    /// Colors of the TUI and charts, overrides theme of the config and NO_COLOR
+    #[arg(long, global = true, value_enum)]
+    theme: Option<Theme>,
+
     #[clap(subcommand)]
     command: Option<Commands>,
 }
 
+impl Cli {
+    fn cache_mode(&self) -> CacheMode {
+        if self.no_cache {
+            CacheMode::Disabled
+        } else if self.refresh {
+            CacheMode::Refresh
+        } else {
+            CacheMode::Enabled
+        }
+    }
+
+    fn range(&self) -> DateRange {
+        DateRange::new(self.since.clone(), self.until.clone())
+    }
+
+    // Settings of the config file and environment variables, overridden by flags
+    fn config(&self) -> Result<Config, String> {
+        let path = self.config.clone().or(env::var(CONFIG_ENV).ok());
+        let mut config = Config::load(path.as_deref())?;
+        config.tick_rate = self.tick_rate.unwrap_or(config.tick_rate);
+        config.api_timeout = self.api_timeout.unwrap_or(config.api_timeout);
+        config.proxy = self.proxy.clone().unwrap_or(config.proxy);
+        config.theme = self.theme.unwrap_or(config.theme);
+        Ok(match &self.export_dir {
+            Some(dir) => config.with_export_dir(dir),
+            None => config,
+        })
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Initialize Shodan API key, grab it from https://account.shodan.io
     Init { key: String },
+    /// Search without launching the TUI and print results to stdout
+    Search {
+        /// Search query used to search the historical database, e.g. "product:nginx port:443"
+        #[arg(long)]
+        query: String,
+
+        /// A comma-separated list of properties to get summary information on, e.g. country:10
+        #[arg(long, default_value = "")]
+        facets: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Search and draw the line chart to stdout, lines are facet values if requested facets
+    Plot {
+        /// Search query used to search the historical database, e.g. "product:nginx port:443"
+        #[arg(long)]
+        query: String,
+
+        /// A comma-separated list of properties to get summary information on, e.g. country:10
+        #[arg(long, default_value = "")]
+        facets: String,
+
+        #[command(flatten)]
+        args: PlotArgs,
+    },
+    /// Manage saved sessions
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SessionCommands {
+    /// List saved sessions
+    List,
+    /// Delete a saved session
+    Delete { name: String },
+}
+
+#[derive(Debug, Args)]
+struct PlotArgs {
+    /// Chart width, default to terminal width
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    width: Option<u16>,
+
+    /// Chart height, default to terminal height
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    height: Option<u16>,
+
+    /// Draw lines with braille or plain ASCII characters
+    #[arg(long, value_enum, default_value_t = PlotMarker::Braille)]
+    marker: PlotMarker,
+
+    /// Number of facet values to draw
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+}
+
+// Exit codes of headless commands:
+// 0 success, 1 invalid arguments or missing API key, 2 no results, 3 API errors, 4 network errors
+fn fetch(
+    query: &str,
+    facets: &str,
+    range: &DateRange,
+    cache: &ResponseCache,
+    config: &Config,
+) -> Result<SearchResponse, i32> {
+    let api_key = match get_api_key() {
+        Ok(key) => key,
+        Err(_) => {
+            eprintln!("Error: Missing API key, please run \"strend init <API key>\"");
+            return Err(EXIT_ERROR_CODE);
+        }
+    };
+
+    if query.trim().is_empty() {
+        eprintln!("Error: Invalid search query");
+        return Err(EXIT_ERROR_CODE);
+    }
+
+    let client = TrendsClient::from_env(&api_key)
+        .with_timeout(Duration::from_secs(config.api_timeout))
+        .with_proxy(&config.proxy);
+    match cache.search(&client, query, facets) {
+        Ok(response) => {
+            let response = range.filter(&response);
+            if response.total == 0 {
+                eprintln!("No results found");
+                return Err(EXIT_NO_RESULTS_CODE);
+            }
+            Ok(response)
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            match err {
+                ApiError::Timeout | ApiError::Transport(_) => Err(EXIT_NETWORK_ERROR_CODE),
+                ApiError::Status { .. } | ApiError::Parse(_) => Err(EXIT_API_ERROR_CODE),
+            }
+        }
+    }
+}
+
+fn search(
+    query: &str,
+    facets: &str,
+    range: &DateRange,
+    format: OutputFormat,
+    cache: &ResponseCache,
+    config: &Config,
+) -> i32 {
+    match fetch(query, facets, range, cache, config) {
+        Ok(response) => {
+            print!("{}", format_search(query, facets, &response, format));
+            EXIT_SUCCESS_CODE
+        }
+        Err(code) => code,
+    }
+}
+
+fn plot(
+    query: &str,
+    facets: &str,
+    range: &DateRange,
+    args: &PlotArgs,
+    cache: &ResponseCache,
+    config: &Config,
+) -> i32 {
+    let response = match fetch(query, facets, range, cache, config) {
+        Ok(response) => response,
+        Err(code) => return code,
+    };
+
+    match Chart::from_response(query, facets, &response) {
+        Ok(chart) => {
+            // Fit current terminal if size is not given
+            let (columns, rows) = terminal::size().unwrap_or((80, 24));
+            let mut title = match facets.is_empty() {
+                true => format!("Query: {}", query),
+                false => format!("Query: {}, Facets: {}", query, facets),
+            };
+            if !range.is_empty() {
+                title = format!("{}, Months: {}", title, range);
+            }
+            // Leave rows for the title, the facet name and the legend, keep a few rows for the chart
+            // but not more than the terminal
+            let reserved = match chart.facets.is_empty() {
+                true => 2,
+                false => (args.top as u16).saturating_add(2),
+            };
+            let height = args
+                .height
+                .unwrap_or(rows.saturating_sub(reserved).max(10).min(rows).max(1));
+            print!(
+                "{}",
+                plot::plot(
+                    &title,
+                    &chart,
+                    args.top,
+                    args.width.unwrap_or(columns.max(1)),
+                    height,
+                    args.marker,
+                    // No colors if NO_COLOR or monochrome
+                    &match io::stdout().is_terminal() && config.theme != Theme::Monochrome {
+                        true => config.line_colors(),
+                        false => vec![],
+                    }
+                )
+            );
+            EXIT_SUCCESS_CODE
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            EXIT_API_ERROR_CODE
+        }
+    }
 }
 
 fn main() -> AppResult<()> {
     let cli = Cli::parse();
+    let cache = ResponseCache::new(cli.cache_mode());
+    // Only searches use the config, init can fix the setup with an invalid config
+    let config = || match cli.config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(EXIT_ERROR_CODE);
+        }
+    };
+
+    if let Err(err) = DateRange::parse(&cli.range().to_string()) {
+        eprintln!("Error: {}", err);
+        std::process::exit(EXIT_ERROR_CODE);
+    }
 
     match &cli.command {
         Some(Commands::Init { key }) => {
             init_api_key(key.to_string(), true)?;
             std::process::exit(EXIT_SUCCESS_CODE);
         }
+        Some(Commands::Search {
+            query,
+            facets,
+            format,
+        }) => {
+            std::process::exit(search(
+                query,
+                facets.trim(),
+                &cli.range(),
+                *format,
+                &cache,
+                &config(),
+            ));
+        }
+        Some(Commands::Plot {
+            query,
+            facets,
+            args,
+        }) => {
+            std::process::exit(plot(
+                query,
+                facets.trim(),
+                &cli.range(),
+                args,
+                &cache,
+                &config(),
+            ));
+        }
+        Some(Commands::Session { command }) => {
+            let store = SessionStore::new();
+            let result = match command {
+                SessionCommands::List => store.list().map(|names| {
+                    for name in names {
+                        println!("{}", name);
+                    }
+                }),
+                SessionCommands::Delete { name } => store.delete(name),
+            };
+
+            if let Err(err) = result {
+                println!("Error: {}", err);
+                std::process::exit(EXIT_ERROR_CODE);
+            }
+            std::process::exit(EXIT_SUCCESS_CODE);
+        }
         None => {}
     }
 
-    let query = cli.query.unwrap_or(String::new());
-    let facets = cli.facets.unwrap_or(String::new());
+    let query = cli.query.clone().unwrap_or_default();
+    let facets = cli.facets.clone().unwrap_or_default();
     // Shared data to run API requests in separate thread so it's not block application
     let (sender, receiver) = mpsc::channel();
 
@@ -58,17 +393,40 @@ fn main() -> AppResult<()> {
         std::process::exit(EXIT_ERROR_CODE);
     }
 
-    // Create an application.
+    // Create an application, facets of the config if no --facets
+    let config = config();
+    let facets = cli.facets.clone().unwrap_or(config.facets.clone());
     let mut app = App::new(query, facets, receiver);
+    app.configure(config);
+    app.cache = cache;
+    app.range_input.set_input(&cli.range().to_string());
+    app.line_chart.rolling = cli.rolling as usize;
+    app.load_history();
+    app.load_completions();
     let mut state: AppState = AppState {
         focused: true,
         submitted: false,
         first_render: true,
         facet_indexes: HashMap::new(),
+        facet_tabs: HashMap::new(),
         app_log: String::new(),
         sender,
+        keymap: app.config.keymap(),
     };
 
+    // Restore saved session in the background, new session will be saved on exit
+    let sessions = SessionStore::new();
+    if let Some(name) = &cli.session {
+        match sessions.load(name) {
+            Ok(Some(session)) => app.restore_session(session),
+            Ok(None) => {}
+            Err(err) => {
+                println!("Error: Failed to load session {} ({})", name, err);
+                std::process::exit(EXIT_ERROR_CODE);
+            }
+        }
+    }
+
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
@@ -86,12 +444,14 @@ fn main() -> AppResult<()> {
         match tui.events.next()? {
             Event::Tick => {
                 let _ = app.tick();
+                app.receive_session(&mut state);
             }
-            Event::Key(event) => {
-                // Skip process events on waiting for API response
-                if app.blocking == 0 {
-                    handle_events(CrosstermEvent::Key(event), &mut app, &mut state)?
-                }
+            // Skip process events on waiting for API response
+            Event::Key(event) if app.blocking == 0 => {
+                handle_events(CrosstermEvent::Key(event), &mut app, &mut state)?
+            }
+            Event::Mouse(event) if app.blocking == 0 => {
+                handle_events(CrosstermEvent::Mouse(event), &mut app, &mut state)?
             }
             _ => {}
         }
@@ -99,6 +459,13 @@ fn main() -> AppResult<()> {
 
     // Exit the user interface.
     tui.exit()?;
+
+    if let Some(name) = &cli.session {
+        if let Err(err) = sessions.save(name, &app.session(&state)) {
+            println!("Error: Failed to save session {} ({})", name, err);
+            std::process::exit(EXIT_ERROR_CODE);
+        }
+    }
     Ok(())
 }
 
@@ -0,0 +1,143 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::api::{FacetMonth, MonthCount, SearchResponse};
+
+/// Output formats of headless commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchReport<'a> {
+    query: &'a str,
+    facets: &'a str,
+    total: i64,
+    matches: &'a [MonthCount],
+    facet_buckets: &'a BTreeMap<String, Vec<FacetMonth>>,
+}
+
+/// Formats search response to print out, one row per month.
+///
+/// Month   | Total    | os:Linux | os:Ubuntu
+/// 2017-06 | 19799459 | 8345     | 1234
+pub fn format_search(
+    query: &str,
+    facets: &str,
+    response: &SearchResponse,
+    format: OutputFormat,
+) -> String {
+    match format {
+        OutputFormat::Json => {
+            let report = SearchReport {
+                query,
+                facets,
+                total: response.total,
+                matches: &response.matches,
+                facet_buckets: &response.facets,
+            };
+            // Serialize plain structs won't fail
+            serde_json::to_string_pretty(&report).unwrap_or_default() + "\n"
+        }
+        OutputFormat::Csv => search_rows(response)
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| escape_csv(cell))
+                    .collect::<Vec<String>>()
+                    .join(",")
+                    + "\n"
+            })
+            .collect(),
+        OutputFormat::Table => {
+            let mut output = format!("Query: {}\n", query);
+            if !facets.is_empty() {
+                output += &format!("Facets: {}\n", facets);
+            }
+            output += &format!("Total: {}\n\n", response.total);
+            output += &format_table(&search_rows(response));
+            output
+        }
+    }
+}
+
+// Build rows of month, total and count of each facet value
+fn search_rows(response: &SearchResponse) -> Vec<Vec<String>> {
+    let mut header = vec!["Month".to_string(), "Total".to_string()];
+    let mut rows: Vec<Vec<String>> = response
+        .matches
+        .iter()
+        .map(|item| vec![item.month.to_owned(), item.count.to_string()])
+        .collect();
+
+    for (facet, months) in response.facets.iter() {
+        // Facet values has most records first
+        let mut totals: Vec<(&str, i64)> = vec![];
+        for month in months {
+            for bucket in &month.values {
+                match totals.iter_mut().find(|(value, _)| *value == bucket.value) {
+                    Some((_, total)) => *total += bucket.count,
+                    None => totals.push((bucket.value.as_str(), bucket.count)),
+                }
+            }
+        }
+        totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+
+        for (value, _) in totals.iter() {
+            header.push(format!("{}:{}", facet, value));
+        }
+
+        for (row, item) in rows.iter_mut().zip(response.matches.iter()) {
+            let buckets = months.iter().find(|month| month.month == item.month);
+            for (value, _) in totals.iter() {
+                let count = buckets
+                    .and_then(|month| month.values.iter().find(|bucket| bucket.value == *value))
+                    .map(|bucket| bucket.count)
+                    .unwrap_or(0);
+                row.push(count.to_string());
+            }
+        }
+    }
+
+    rows.insert(0, header);
+    rows
+}
+
+/// Align columns with spaces, first row is header.
+pub fn format_table(rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = vec![];
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(max_width) => *max_width = (*max_width).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect();
+        output += line.join("  ").trim_end();
+        output += "\n";
+    }
+    output
+}
+
+/// Quote CSV cell if needed, e.g. Amazon.com, Inc. -> "Amazon.com, Inc."
+pub fn escape_csv(cell: &str) -> String {
+    if cell.contains(',') || cell.contains('"') || cell.contains('\n') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
@@ -0,0 +1,165 @@
+use clap::ValueEnum;
+use crossterm::style::{ResetColor, SetForegroundColor};
+use ratatui::buffer::Buffer;
+use ratatui::prelude::*;
+use ratatui::widgets::{
+    Axis, Block, Borders, Chart as LineChart, Dataset, GraphType, Padding, Widget,
+};
+
+use crate::app::Chart;
+
+/// Markers used to draw chart lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PlotMarker {
+    Braille,
+    /// Plain ASCII for terminals/ fonts without Unicode support
+    Ascii,
+}
+
+/// Renders the chart to text without the TUI, the lines are colored by `colors`, plain text if empty.
+///
+/// Plot facet values of each requested facet if the chart has facets, otherwise plot total results.
+pub fn plot(
+    title: &str,
+    chart: &Chart,
+    top: usize,
+    width: u16,
+    height: u16,
+    marker: PlotMarker,
+    colors: &[Color],
+) -> String {
+    let mut output = format!("{}\n", title);
+
+    if chart.facets.is_empty() {
+        output += &plot_chart(chart, top, width, height, marker, colors);
+    } else {
+        for (index, chart) in chart.facets.iter().enumerate() {
+            if index > 0 {
+                output += "\n";
+            }
+            output += &format!("Facet: {}\n", chart.name);
+            output += &plot_chart(chart, top, width, height, marker, colors);
+        }
+    }
+
+    output
+}
+
+fn plot_chart(
+    chart: &Chart,
+    top: usize,
+    width: u16,
+    height: u16,
+    marker: PlotMarker,
+    colors: &[Color],
+) -> String {
+    let color = !colors.is_empty();
+    let line_color = |index: usize| match color {
+        true => colors[index % colors.len()],
+        false => Color::Reset,
+    };
+    let points: Vec<_> = chart.datasets.iter().take(top.max(1)).collect();
+
+    let datasets: Vec<Dataset> = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            Dataset::default()
+                .marker(match marker {
+                    PlotMarker::Braille => symbols::Marker::Braille,
+                    PlotMarker::Ascii => symbols::Marker::Dot,
+                })
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(line_color(index)))
+                .data(&point.data)
+        })
+        .collect();
+
+    let line_chart = LineChart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::NONE)
+                .padding(Padding::new(0, 1, 0, 0)),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([chart.x_bounds[0], chart.x_bounds[chart.x_bounds.len() - 1]])
+                .labels(chart.x_ticks.iter().cloned().map(Span::from).collect()),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([chart.y_bounds[0], chart.y_bounds[chart.y_bounds.len() - 1]])
+                .labels(chart.y_ticks.iter().cloned().map(Span::from).collect())
+                .labels_alignment(Alignment::Right),
+        );
+
+    let area = Rect::new(0, 0, width, height);
+    let mut buffer = Buffer::empty(area);
+    line_chart.render(area, &mut buffer);
+
+    let mut output = buffer_to_string(&buffer, marker, color);
+
+    // Legend
+    for (index, point) in points.iter().enumerate() {
+        let symbol = match marker {
+            PlotMarker::Braille => "\u{25a0}", //■
+            PlotMarker::Ascii => "*",
+        };
+        let line = format!("{} {} ({})", symbol, point.label, point.total);
+        output += &colorize(&line, line_color(index), color);
+        output += "\n";
+    }
+
+    output
+}
+
+fn buffer_to_string(buffer: &Buffer, marker: PlotMarker, color: bool) -> String {
+    let mut output = String::new();
+
+    for cells in buffer.content.chunks(buffer.area.width as usize) {
+        let mut line = String::new();
+        let mut fg = Color::Reset;
+
+        // Skip trailing spaces
+        let end = cells
+            .iter()
+            .rposition(|cell| cell.symbol != " ")
+            .map_or(0, |index| index + 1);
+
+        for cell in &cells[..end] {
+            if color && cell.fg != fg {
+                fg = cell.fg;
+                line += &match fg {
+                    Color::Reset => ResetColor.to_string(),
+                    _ => SetForegroundColor(fg.into()).to_string(),
+                };
+            }
+
+            line += match marker {
+                PlotMarker::Braille => cell.symbol.as_str(),
+                PlotMarker::Ascii => match cell.symbol.as_str() {
+                    symbols::DOT => "*",
+                    symbols::line::VERTICAL => "|",
+                    symbols::line::HORIZONTAL => "-",
+                    symbols::line::BOTTOM_LEFT => "+",
+                    symbol => symbol,
+                },
+            };
+        }
+
+        if color && fg != Color::Reset {
+            line += &ResetColor.to_string();
+        }
+        output += &line;
+        output += "\n";
+    }
+
+    output
+}
+
+fn colorize(text: &str, fg: Color, color: bool) -> String {
+    match color {
+        true => format!("{}{}{}", SetForegroundColor(fg.into()), text, ResetColor),
+        false => text.to_owned(),
+    }
+}
@@ -0,0 +1,267 @@
+/// Known filter names, https://www.shodan.io/search/filters, names fetched from the API are
+/// added by [`crate::completion::Completions`].
+pub const FILTERS: [&str; 86] = [
+    "after",
+    "all",
+    "asn",
+    "before",
+    "bitcoin.ip",
+    "bitcoin.ip_count",
+    "bitcoin.port",
+    "bitcoin.version",
+    "city",
+    "cloud.provider",
+    "cloud.region",
+    "cloud.service",
+    "country",
+    "cpe",
+    "device",
+    "geo",
+    "has_ipv6",
+    "has_screenshot",
+    "has_ssl",
+    "has_vuln",
+    "hash",
+    "hostname",
+    "html",
+    "http.component",
+    "http.component_category",
+    "http.dom_hash",
+    "http.favicon.hash",
+    "http.headers_hash",
+    "http.html",
+    "http.html_hash",
+    "http.robots_hash",
+    "http.securitytxt",
+    "http.status",
+    "http.title",
+    "http.waf",
+    "ip",
+    "isp",
+    "link",
+    "net",
+    "ntp.ip",
+    "ntp.ip_count",
+    "ntp.more",
+    "ntp.port",
+    "org",
+    "os",
+    "port",
+    "postal",
+    "product",
+    "region",
+    "scan",
+    "screenshot.hash",
+    "screenshot.label",
+    "shodan.module",
+    "snmp.contact",
+    "snmp.location",
+    "snmp.name",
+    "ssh.hassh",
+    "ssh.type",
+    "ssl",
+    "ssl.alpn",
+    "ssl.cert.alg",
+    "ssl.cert.expired",
+    "ssl.cert.extension",
+    "ssl.cert.fingerprint",
+    "ssl.cert.issuer.cn",
+    "ssl.cert.pubkey.bits",
+    "ssl.cert.pubkey.type",
+    "ssl.cert.serial",
+    "ssl.cert.subject.cn",
+    "ssl.chain_count",
+    "ssl.cipher.bits",
+    "ssl.cipher.name",
+    "ssl.cipher.version",
+    "ssl.ja3s",
+    "ssl.jarm",
+    "ssl.version",
+    "state",
+    "tag",
+    "telnet.do",
+    "telnet.dont",
+    "telnet.option",
+    "telnet.will",
+    "telnet.wont",
+    "title",
+    "version",
+    "vuln",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Known filter name with the colon, e.g. port:
+    Filter,
+    /// Unknown filter name with the colon, e.g. prot:
+    UnknownFilter,
+    /// Minus sign before filter, e.g. -port:22
+    Negation,
+    /// Filter value, e.g. 443
+    Value,
+    /// Quoted filter value, e.g. "Amazon.com, Inc."
+    QuotedValue,
+    /// Free text search, e.g. nginx
+    Text,
+    Whitespace,
+}
+
+/// Part of the query, `start` and `end` are byte indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits search query into tokens, the tokens cover the whole query.
+///
+/// product:nginx -port:22 org:"Amazon.com, Inc." => Filter Value Whitespace Negation Filter Value ...
+///
+/// Filter names not in `filters` are unknown.
+pub fn tokenize(query: &str, filters: &[String]) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut start = 0;
+
+    while start < query.len() {
+        let rest = &query[start..];
+        let end = start + word_len(rest);
+
+        // Whitespace between words
+        if end == start {
+            let len = rest.len() - rest.trim_start().len();
+            tokens.push(Token {
+                kind: TokenKind::Whitespace,
+                start,
+                end: start + len,
+            });
+            start += len;
+            continue;
+        }
+
+        let word = &query[start..end];
+        let negated = word.starts_with('-');
+        let name_start = start + negated as usize;
+
+        match filter_name(&query[name_start..end]) {
+            Some(name) => {
+                if negated {
+                    tokens.push(Token {
+                        kind: TokenKind::Negation,
+                        start,
+                        end: name_start,
+                    });
+                }
+                let value_start = name_start + name.len() + 1;
+                tokens.push(Token {
+                    kind: match is_filter(name, filters) {
+                        true => TokenKind::Filter,
+                        false => TokenKind::UnknownFilter,
+                    },
+                    start: name_start,
+                    end: value_start,
+                });
+                if value_start < end {
+                    tokens.push(Token {
+                        kind: match query[value_start..end].starts_with('"') {
+                            true => TokenKind::QuotedValue,
+                            false => TokenKind::Value,
+                        },
+                        start: value_start,
+                        end,
+                    });
+                }
+            }
+            None => tokens.push(Token {
+                kind: TokenKind::Text,
+                start,
+                end,
+            }),
+        }
+        start = end;
+    }
+
+    tokens
+}
+
+/// Warning of unknown filters, unclosed quotes or empty values, `None` if the query looks fine.
+pub fn check_query(query: &str, filters: &[String]) -> Option<String> {
+    if query.matches('"').count() % 2 == 1 {
+        return Some("Unclosed quote in query".to_string());
+    }
+
+    for token in tokenize(query, filters) {
+        let text = &query[token.start..token.end];
+        match token.kind {
+            TokenKind::UnknownFilter => {
+                return Some(format!("Unknown filter: {}", text.trim_end_matches(':')))
+            }
+            TokenKind::Filter if !has_value(query, &token) => {
+                return Some(format!("Missing value of filter: {}", text))
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Warning of invalid comma-separated facets, each facet should have a limit, e.g. country:10
+pub fn check_facets(facets: &str) -> Option<String> {
+    if facets.trim().is_empty() {
+        return None;
+    }
+
+    for facet in facets.split(',').map(|facet| facet.trim()) {
+        let warning = match facet.split_once(':') {
+            _ if facet.is_empty() => "Empty facet".to_string(),
+            None => format!("Missing limit of facet: {}, e.g. {}:10", facet, facet),
+            Some(("", _)) => format!("Missing name of facet: {}", facet),
+            Some((name, limit)) if !limit.parse::<u32>().is_ok_and(|limit| limit > 0) => {
+                format!("Invalid limit of facet: {}, e.g. {}:10", facet, name)
+            }
+            _ => continue,
+        };
+        return Some(warning);
+    }
+
+    None
+}
+
+pub fn is_filter(name: &str, filters: &[String]) -> bool {
+    filters.iter().any(|filter| filter == name)
+}
+
+// Length of next word, spaces inside quotes are part of the word
+fn word_len(text: &str) -> usize {
+    let mut quoted = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => return index,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+// Filter name before the colon, e.g. ssl.cert.subject.cn in ssl.cert.subject.cn:example.com
+fn filter_name(word: &str) -> Option<&str> {
+    let (name, _) = word.split_once(':')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+
+    match valid {
+        true => Some(name),
+        false => None,
+    }
+}
+
+fn has_value(query: &str, filter: &Token) -> bool {
+    query[filter.end..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace())
+}
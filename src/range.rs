@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::api::SearchResponse;
+
+/// Months to keep in search results, e.g. 2022-01..2023-06, both ends are optional and inclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl DateRange {
+    pub fn new(since: Option<String>, until: Option<String>) -> Self {
+        Self { since, until }
+    }
+
+    /// Parse range in the searchbox, e.g. 2022-01..2023-06, 2022-01.. or ..2023-06
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (since, until) = text.split_once("..").unwrap_or((text, ""));
+        let month = |month: &str| match month.trim() {
+            "" => Ok(None),
+            month => parse_month(month).map(Some),
+        };
+
+        let range = Self::new(month(since)?, month(until)?);
+        match (&range.since, &range.until) {
+            (Some(since), Some(until)) if since > until => Err(format!(
+                "Invalid months range: {} is after {}",
+                since, until
+            )),
+            _ => Ok(range),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// If the month (YYYY-MM) is in the range.
+    pub fn contains(&self, month: &str) -> bool {
+        self.since
+            .as_ref()
+            .map_or(true, |since| month >= since.as_str())
+            && self
+                .until
+                .as_ref()
+                .map_or(true, |until| month <= until.as_str())
+    }
+
+    /// Search response with only months in the range, the total is of the kept months.
+    pub fn filter(&self, response: &SearchResponse) -> SearchResponse {
+        let mut response = response.clone();
+        if !self.is_empty() {
+            response.matches.retain(|item| self.contains(&item.month));
+            response.total = response.matches.iter().map(|item| item.count).sum();
+            for months in response.facets.values_mut() {
+                months.retain(|item| self.contains(&item.month));
+            }
+        }
+        response
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+        write!(
+            f,
+            "{}..{}",
+            self.since.as_deref().unwrap_or_default(),
+            self.until.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Validate month in YYYY-MM format, also used to parse `--since` and `--until` flags.
+pub fn parse_month(month: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid month {}, e.g. 2022-01", month);
+    let (year, month_num) = month.split_once('-').ok_or_else(invalid)?;
+
+    let valid = year.len() == 4
+        && year.chars().all(|c| c.is_ascii_digit())
+        && month_num.len() == 2
+        && matches!(month_num.parse::<u8>(), Ok(1..=12));
+    match valid {
+        true => Ok(month.to_string()),
+        false => Err(invalid()),
+    }
+}
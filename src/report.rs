@@ -0,0 +1,171 @@
+use std::time::SystemTime;
+
+use crate::app::{decode_query, decode_range, App, Chart};
+use crate::image::{escape_xml, ChartImage, ImageLine};
+use crate::theme::Theme;
+use crate::util::datetime;
+
+// Lines of facet charts, all facet values are still listed in the tables
+const FACET_LINES: usize = 10;
+
+const STYLE: &str = "
+body { font-family: Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 1200px; color: #222; }
+section { border-top: 1px solid #ddd; margin-top: 2em; }
+svg { max-width: 100%; height: auto; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { padding: 4px 12px; border-bottom: 1px solid #eee; text-align: left; }
+td.count { text-align: right; font-variant-numeric: tabular-nums; }
+.meta, .meta th { color: #666; }
+.legend { cursor: pointer; }
+.off { opacity: 0.15; }
+circle { opacity: 0; }
+circle:hover { opacity: 1; }
+";
+
+// Click a legend to hide/ show its line, hover points to show counts
+const SCRIPT: &str = "
+document.querySelectorAll('svg').forEach((svg) => {
+  const lines = svg.querySelectorAll('.line');
+  svg.querySelectorAll('.legend').forEach((legend, index) => {
+    legend.addEventListener('click', () => {
+      legend.classList.toggle('off');
+      lines[index].classList.toggle('off');
+    });
+  });
+});
+";
+
+/// Single HTML file of saved queries with their charts and facet values, styles and scripts are
+/// inline so it works offline.
+pub fn html_report(app: &App, time: SystemTime) -> String {
+    let mut html = vec![
+        "<!DOCTYPE html>".to_string(),
+        "<html lang=\"en\">".to_string(),
+        "<head>".to_string(),
+        "<meta charset=\"utf-8\">".to_string(),
+        "<title>Shodan Trends report</title>".to_string(),
+        format!("<style>{}</style>", STYLE),
+        "</head>".to_string(),
+        "<body>".to_string(),
+        "<h1>Shodan Trends report</h1>".to_string(),
+        format!(
+            "<p class=\"meta\">Generated at {} by strend {}, {} saved queries</p>",
+            datetime(time),
+            env!("CARGO_PKG_VERSION"),
+            app.charts.len()
+        ),
+    ];
+
+    // Latest query first with the same colors as saved queries block
+    let colors = app.config.line_colors();
+    let dashed = app.config.theme == Theme::Monochrome;
+    for (index, query) in app.queries.iter().rev().enumerate() {
+        let Some(chart) = app.charts.get(query) else {
+            continue;
+        };
+        let (search, facets) = decode_query(query);
+        let range = match decode_range(query) {
+            range if range.is_empty() => "All".to_string(),
+            range => range.to_string(),
+        };
+
+        html.push("<section>".to_string());
+        html.push(format!("<h2>{}</h2>", escape_xml(&search)));
+        html.push("<table class=\"meta\">".to_string());
+        for (name, value) in [
+            ("Query", search.as_str()),
+            ("Facets", facets.as_str()),
+            ("Months", range.as_str()),
+            ("Saved query", query.as_str()),
+        ] {
+            html.push(format!(
+                "<tr><th>{}</th><td><code>{}</code></td></tr>",
+                name,
+                escape_xml(value)
+            ));
+        }
+        if let Some(searched) = app.searched.get(query) {
+            html.push(format!(
+                "<tr><th>Searched at</th><td>{}</td></tr>",
+                datetime(*searched)
+            ));
+        }
+        html.push("</table>".to_string());
+
+        let total = ImageLine {
+            label: search.to_owned(),
+            color: colors[index % colors.len()],
+            points: chart.datasets[0].data.to_owned(),
+        };
+        html.push(chart_image("Total results", chart, vec![total], dashed).svg());
+
+        for facet in &chart.facets {
+            html.push(format!("<h3>Facet: {}</h3>", escape_xml(&facet.name)));
+            let lines = facet
+                .datasets
+                .iter()
+                .take(FACET_LINES)
+                .enumerate()
+                .map(|(index, points)| ImageLine {
+                    label: points.label.to_owned(),
+                    color: colors[index % colors.len()],
+                    points: points.data.to_owned(),
+                })
+                .collect();
+            html.push(chart_image(&facet.name, facet, lines, dashed).svg());
+            html.push(facet_table(facet));
+        }
+        html.push("</section>".to_string());
+    }
+
+    html.push(format!("<script>{}</script>", SCRIPT));
+    html.push("</body>".to_string());
+    html.push("</html>".to_string());
+    html.join("\n") + "\n"
+}
+
+// Whole history of the chart, counts are shown on hovering points
+fn chart_image(title: &str, chart: &Chart, lines: Vec<ImageLine>, dashed: bool) -> ChartImage {
+    let bounds = |bounds: &[f64]| match (bounds.first(), bounds.last()) {
+        (Some(first), Some(last)) => [*first, *last],
+        _ => [0.0, 0.0],
+    };
+    ChartImage {
+        title: title.to_string(),
+        y_title: String::new(),
+        x_bounds: bounds(&chart.x_bounds),
+        x_ticks: chart.x_ticks.to_owned(),
+        x_labels: chart.x_labels.to_owned(),
+        y_bounds: bounds(&chart.y_bounds),
+        y_ticks: chart.y_ticks.to_owned(),
+        stacked: false,
+        dashed,
+        lines,
+    }
+}
+
+// Facet values with their totals and counts of the latest month
+fn facet_table(facet: &Chart) -> String {
+    let latest = facet.x_labels.last().cloned().unwrap_or_default();
+    let mut table = vec![
+        "<table>".to_string(),
+        format!(
+            "<tr><th>#</th><th>Value</th><th>Total</th><th>{}</th></tr>",
+            escape_xml(&latest)
+        ),
+    ];
+    for (index, points) in facet.datasets.iter().enumerate() {
+        table.push(format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"count\">{}</td><td class=\"count\">{}</td></tr>",
+            index + 1,
+            escape_xml(&points.label),
+            points.total,
+            points
+                .data
+                .last()
+                .map_or(String::new(), |point| point.1.to_string())
+        ));
+    }
+    table.push("</table>".to_string());
+    table.join("\n")
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, prelude::*};
+use std::path::PathBuf;
+
+use crate::app::FacetIndex;
+use crate::util;
+
+/// Saved queries and their selections, restored on launch with `--session <name>`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub queries: Vec<String>, // Encoded queries, e.g. query=nginx&facets=os%3A5
+    pub selected_queries: Vec<String>, // Checked queries in saved queries block
+    pub highlighted_query: Option<String>, // Current highlighted query in saved queries block
+    pub facet_indexes: HashMap<String, FacetIndex>,
+    pub facet_tabs: HashMap<String, usize>,
+}
+
+/// Session files in the config directory, e.g. ~/.config/shodan/strend/sessions/<name>.json
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self {
+            dir: PathBuf::from(format!("{}/sessions", util::get_data_dir())),
+        }
+    }
+
+    pub fn with_dir(mut self, dir: PathBuf) -> Self {
+        self.dir = dir;
+        self
+    }
+
+    /// Load saved session, `None` if it's a new session.
+    pub fn load(&self, name: &str) -> io::Result<Option<Session>> {
+        let mut file = match File::open(self.path(name)?) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self, name: &str, session: &Session) -> io::Result<()> {
+        let path = self.path(name)?;
+        create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(session)?;
+        File::create(path)?.write_all(content.as_bytes())
+    }
+
+    /// Names of saved sessions, sorted alphabetically.
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .map(|name| name.to_owned())
+            })
+            .collect();
+        names.sort();
+
+        Ok(names)
+    }
+
+    pub fn delete(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(name)?)
+    }
+
+    // Only allow simple names so session files can't be written outside sessions directory
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        match valid {
+            true => Ok(self.dir.join(format!("{}.json", name))),
+            false => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Session name only contains letters, numbers, - and _",
+            )),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use human_repr::HumanCount;
+
+use crate::app::Chart;
+
+/// Summary of a query in the visible months.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Month label and count
+    pub latest: (String, f64),
+    pub min: (String, f64),
+    pub max: (String, f64),
+    /// Change from the first to the latest month
+    pub change: f64,
+    pub change_percent: Option<f64>,
+    /// Compound annual growth rate in percent
+    pub cagr: Option<f64>,
+    /// Facet values of the largest gains and losses
+    pub movers: Vec<Mover>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mover {
+    pub label: String,
+    pub change: f64,
+    pub change_percent: Option<f64>,
+    /// Mover by percent change instead of absolute change
+    pub relative: bool,
+}
+
+impl Stats {
+    /// Stats of the total chart and movers of the facet chart between the first and last month
+    /// labels, e.g. Jun 2017 and Jul 2020. All months if the labels aren't in the chart.
+    pub fn new(chart: &Chart, facet: Option<&Chart>, first: &str, last: &str) -> Option<Stats> {
+        let (start, end) = window(&chart.x_labels, first, last);
+        let data = chart.datasets.first()?.data.get(start..=end)?;
+        let label = |x: f64| chart.x_labels[x as usize].to_owned();
+
+        let latest = data.last()?;
+        let min = data
+            .iter()
+            .fold(&data[0], |min, point| match point.1 < min.1 {
+                true => point,
+                false => min,
+            });
+        let max = data
+            .iter()
+            .fold(&data[0], |max, point| match point.1 > max.1 {
+                true => point,
+                false => max,
+            });
+        let (first, last) = (data[0].1, latest.1);
+
+        // Annualized over calendar months between the first and latest month as there could be
+        // months without data
+        let months = match (chart.months.get(start), chart.months.get(end)) {
+            (Some(first), Some(last)) => months_between(first, last),
+            _ => Some(end - start),
+        };
+        let cagr = match months {
+            Some(months) if first > 0.0 && last > 0.0 && months > 0 => {
+                Some(((last / first).powf(12.0 / months as f64) - 1.0) * 100.0)
+            }
+            _ => None,
+        };
+
+        Some(Stats {
+            latest: (label(latest.0), latest.1),
+            min: (label(min.0), min.1),
+            max: (label(max.0), max.1),
+            change: last - first,
+            change_percent: percent(first, last),
+            cagr,
+            movers: facet
+                .map(|facet| movers(facet, &chart.x_labels[start], &chart.x_labels[end]))
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl Mover {
+    /// Change of the mover, e.g. +1.2k or -40%
+    pub fn value(&self) -> String {
+        match (self.relative, self.change_percent) {
+            (true, Some(percent)) => format_percent(percent),
+            _ => format_change(self.change),
+        }
+    }
+}
+
+// Largest absolute and relative gains and losses of facet values
+fn movers(facet: &Chart, first: &str, last: &str) -> Vec<Mover> {
+    let (start, end) = window(&facet.x_labels, first, last);
+    let changes: Vec<Mover> = facet
+        .datasets
+        .iter()
+        .filter_map(|points| {
+            let (first, last) = (points.data.get(start)?.1, points.data.get(end)?.1);
+            Some(Mover {
+                label: points.label.to_owned(),
+                change: last - first,
+                change_percent: percent(first, last),
+                relative: false,
+            })
+        })
+        .collect();
+
+    let by_change = |a: &&Mover, b: &&Mover| a.change.total_cmp(&b.change);
+    let by_percent = |a: &&Mover, b: &&Mover| {
+        a.change_percent
+            .unwrap_or_default()
+            .total_cmp(&b.change_percent.unwrap_or_default())
+    };
+    let relative = |mover: &Mover| Mover {
+        relative: true,
+        ..mover.clone()
+    };
+
+    let with_percent = changes
+        .iter()
+        .filter(|mover| mover.change_percent.is_some());
+    [
+        changes
+            .iter()
+            .max_by(by_change)
+            .filter(|mover| mover.change > 0.0)
+            .cloned(),
+        changes
+            .iter()
+            .min_by(by_change)
+            .filter(|mover| mover.change < 0.0)
+            .cloned(),
+        with_percent
+            .clone()
+            .max_by(by_percent)
+            .filter(|mover| mover.change > 0.0)
+            .map(relative),
+        with_percent
+            .min_by(by_percent)
+            .filter(|mover| mover.change < 0.0)
+            .map(relative),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+// Indexes of the first and last month labels, all months if not found
+fn window(labels: &[String], first: &str, last: &str) -> (usize, usize) {
+    let position = |label: &str| labels.iter().position(|month| month == label);
+    match (position(first), position(last)) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => (0, labels.len().saturating_sub(1)),
+    }
+}
+
+// Months from the first to the last month in YYYY-MM format
+fn months_between(first: &str, last: &str) -> Option<usize> {
+    let month = |month: &str| {
+        let (year, month) = month.split_once('-')?;
+        Some(year.parse::<usize>().ok()? * 12 + month.parse::<usize>().ok()?)
+    };
+    month(last)?.checked_sub(month(first)?)
+}
+
+fn percent(first: f64, last: f64) -> Option<f64> {
+    match first > 0.0 {
+        true => Some((last - first) / first * 100.0),
+        false => None,
+    }
+}
+
+/// Signed human-readable change, e.g. +1.2k
+pub fn format_change(change: f64) -> String {
+    match change > 0.0 {
+        true => format!("+{}", (change as i64).human_count_bare()),
+        false => (change as i64).human_count_bare().to_string(),
+    }
+}
+
+/// Signed percent, e.g. +12.5%
+pub fn format_percent(percent: f64) -> String {
+    format!("{:+.1}%", percent)
+}
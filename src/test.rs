@@ -9,15 +9,33 @@ use crossterm::event::Event;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyModifiers;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use unicode_width::UnicodeWidthStr;
 
 use ratatui::backend::TestBackend;
 use ratatui::prelude::*;
 use ratatui::Terminal;
 
-use strend::app::{App, AppResult, AppState};
+use strend::api::{parse_search_response, ApiError, TrendsClient};
+use strend::app::{decode_range, encode_query, encode_search, App, AppResult, AppState, Chart};
+use strend::cache::{CacheMode, ResponseCache};
+use strend::completion::{complete, CompletionKind, Completions};
+use strend::components::line_chart::{LineChart, Series, YScale};
+use strend::components::stateful_list::MultiStatefulList;
+use strend::components::user_input::UserInput;
 use strend::components::Component;
+use strend::config::Config;
+use strend::export::{default_path, format_rows, ExportFormat};
 use strend::handler::handle_events;
+use strend::image::{ChartImage, ImageLine};
+use strend::keymap::{Action, Context, KeyChord, Keymap};
+use strend::output::{format_search, OutputFormat};
+use strend::plot::{plot, PlotMarker};
+use strend::query::{check_facets, check_query, tokenize, TokenKind};
+use strend::range::DateRange;
+use strend::session::{Session, SessionStore};
+use strend::stats::Stats;
+use strend::theme::Theme;
 use strend::ui;
 use strend::util;
 
@@ -28,7 +46,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     // Only mock tests if running in Github CI
     if env::var("GITHUB_RUN_ID").is_ok() {
         // Create temp key file as we will mock API requests later
-        if let Err(_) = util::get_api_key() {
+        if util::get_api_key().is_err() {
             util::init_api_key("key".to_string(), false)?;
         }
 
@@ -79,13 +97,17 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     let (sender, receiver) = mpsc::channel();
 
     let mut app = App::new(query, facets, receiver);
+    // Always make API requests
+    app.cache = ResponseCache::new(CacheMode::Disabled);
     let mut state: AppState = AppState {
         focused: true,
         submitted: false,
         first_render: true,
         facet_indexes: HashMap::new(),
+        facet_tabs: HashMap::new(),
         app_log: String::new(),
         sender,
+        keymap: Keymap::default(),
     };
 
     let backend: TestBackend = TestBackend::new(140, 40);
@@ -102,7 +124,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     let mut expected = Buffer::with_lines(vec![
         "┌──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┐",
         "│ Query:                                                                                                                                   │",
-        "│ Facets (optional):                                                                                                                       │",
+        "│ Facets (optional):                                                                                              Months:                  │",
         "└──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┘",
         "┌Info──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┐",
         "│                                                                                                                                          │",
@@ -139,7 +161,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
         "│                                                                                                                                          │",
         "└──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┘",
         "                                                                                                                                            ",
-        "Search [⏎]  Move cursor [←→]  Delete Char [⌫]  Up/ Down [↑↓]  Unfocused [⎋]  Switch panels [⇥]  Exit [^C]                                   ",
+        "Search [⏎]  Move cursor [←→]  Delete Char [⌫]  History [↑↓]  Search history [^R]  Unfocused [⎋]  Switch panels [⇥]  Exit [^C]               ",
     ]);
     println!("{:?}", terminal.backend().buffer());
 
@@ -253,7 +275,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     //     "│                                                                                                                                          │",
     //     "└──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┘",
     //     "                                                                                                                                            ",
-    //     "Search [⏎]  Move cursor [←→]  Delete Char [⌫]  Up/ Down [↑↓]  Unfocused [⎋]  Switch panels [⇥]  Exit [^C]                                   ",
+    //     "Search [⏎]  Move cursor [←→]  Delete Char [⌫]  History [↑↓]  Search history [^R]  Unfocused [⎋]  Switch panels [⇥]  Exit [^C]               ",
     // ]);
 
     // // Style searchbox buffer
@@ -312,10 +334,12 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     assert!(buffer_str.contains("[x] Ubuntu"));
     assert!(buffer_str.contains("[ ] Windows"));
     assert!(buffer_str.contains("Jun 2017"));
-    assert!(buffer_str.contains("Up/ Down [↑↓]"));
+    assert!(buffer_str.contains("History [↑↓]"));
     assert!(!buffer_str.contains("Export [^E]"));
 
-    // Unfocus searchbox will show Export keybinding
+    // Unfocus searchbox will show Export keybinding, switch through months range input
+    app.switch_widgets(&mut state, false)?;
+    assert!(app.range_input.focused());
     app.switch_widgets(&mut state, false)?;
 
     terminal.draw(|frame| {
@@ -326,7 +350,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     let buffer_str = buffer_view(terminal.backend().buffer());
     assert!(buffer_str.contains("Export [^E]"));
 
-    // Export chart data
+    // Export chart data, the dialog has a timestamped file by default
     handle_events(
         Event::Key(KeyEvent::new(KeyCode::Char('E'), KeyModifiers::CONTROL)),
         &mut app,
@@ -337,9 +361,83 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
         ui::render(&mut app, &mut state, frame);
     })?;
 
+    println!("{:?}", terminal.backend().buffer());
+    let buffer_str = buffer_view(terminal.backend().buffer());
+    assert!(buffer_str.contains("Export chart"));
+    assert!(buffer_str.contains("File:   ./strend-"));
+    assert!(buffer_str.contains("Cancel [⎋]"));
+
+    // Choose TSV, then back to CSV and change the file
+    for code in [KeyCode::Down, KeyCode::Up] {
+        handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut app,
+            &mut state,
+        )?;
+    }
+    assert!(app.export_dialog.path.get_input().ends_with(".csv"));
+    let _ = std::fs::remove_file("./data.csv");
+    export_to(&mut app, &mut state, "./data.csv")?;
+
+    terminal.draw(|frame| {
+        ui::render(&mut app, &mut state, frame);
+    })?;
+
     println!("{:?}", terminal.backend().buffer());
     let buffer_str = buffer_view(terminal.backend().buffer());
     assert!(buffer_str.contains("Exported chart to ./data.csv"));
+    assert!(app.export_dialog.hidden());
+
+    // Export image of the displayed chart
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)),
+        &mut app,
+        &mut state,
+    )?;
+    while app.export_dialog.format != ExportFormat::Svg {
+        handle_events(
+            Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::empty())),
+            &mut app,
+            &mut state,
+        )?;
+    }
+    assert!(app.export_dialog.path.get_input().ends_with(".svg"));
+    let _ = std::fs::remove_file("./data.svg");
+    export_to(&mut app, &mut state, "./data.svg")?;
+    let svg = std::fs::read_to_string("./data.svg")?;
+    std::fs::remove_file("./data.svg")?;
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(">Total results: nginx</text>"));
+    assert!(svg.contains(">Jun 2017</text>"));
+    assert!(svg.contains(">nginx</text>"));
+    assert!(svg.contains("stroke=\"#d50527\""));
+
+    // Report of all saved queries
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)),
+        &mut app,
+        &mut state,
+    )?;
+    while app.export_dialog.format != ExportFormat::Html {
+        handle_events(
+            Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::empty())),
+            &mut app,
+            &mut state,
+        )?;
+    }
+    let _ = std::fs::remove_file("./data.html");
+    export_to(&mut app, &mut state, "./data.html")?;
+    let html = std::fs::read_to_string("./data.html")?;
+    std::fs::remove_file("./data.html")?;
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2>nginx</h2>"));
+    assert!(html.contains(
+        "<tr><th>Saved query</th><td><code>query=nginx&amp;facets=os%3A5</code></td></tr>"
+    ));
+    assert!(html.contains("<tr><th>Searched at</th>"));
+    assert!(html.contains("<h3>Facet: os</h3>"));
+    assert!(html.contains("<title>Jun 2017: 19799459</title>"));
+    assert!(!html.contains("src=\"http"));
 
     // Clear application log on next rendering
     app.ticks = 100;
@@ -482,7 +580,8 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     assert!(app.line_chart.data[0].len() > 1);
 
     // Focus Facet values
-    app.select_widget(3);
+    let index = app.get_widget_index(app.facet_values.id());
+    app.select_widget(index);
     assert!(app.facet_values.focused());
 
     // Unselect all lines
@@ -518,12 +617,66 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     assert!(buffer_str.contains("[x] Korea Telecom"));
     assert!(app.line_chart.data[0].len() > 1);
 
-    // Export chart data again
+    // Show share of facet values
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::Char('%'), KeyModifiers::empty())),
+        &mut app,
+        &mut state,
+    )?;
+
+    terminal.draw(|frame| {
+        ui::render(&mut app, &mut state, frame);
+    })?;
+
+    println!("{:?}", terminal.backend().buffer());
+    let buffer_str = buffer_view(terminal.backend().buffer());
+    assert!(app.facet_values.stacked);
+    assert!(buffer_str.contains("% share"));
+    assert!(buffer_str.contains("100%"));
+    assert!(buffer_str.contains("Share [%]"));
+
+    // Untick and tick a value again, areas are still stacked in the order of facet values
+    for code in [KeyCode::Up, KeyCode::Enter, KeyCode::Enter] {
+        handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut app,
+            &mut state,
+        )?;
+    }
+    terminal.draw(|frame| {
+        ui::render(&mut app, &mut state, frame);
+    })?;
+    let labels: Vec<String> = app
+        .line_chart
+        .image
+        .lines
+        .iter()
+        .map(|line| line.label.to_owned())
+        .collect();
+    assert_eq!(labels, app.line_chart.data[0][1..]);
+    assert!(app.line_chart.image.stacked);
+
+    // Export chart data again, ask before overwriting the file
     handle_events(
         Event::Key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)),
         &mut app,
         &mut state,
     )?;
+    export_to(&mut app, &mut state, "./data.csv")?;
+
+    terminal.draw(|frame| {
+        ui::render(&mut app, &mut state, frame);
+    })?;
+
+    println!("{:?}", terminal.backend().buffer());
+    let buffer_str = buffer_view(terminal.backend().buffer());
+    assert!(buffer_str.contains("File exists, press ⏎ to overwrite"));
+
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty())),
+        &mut app,
+        &mut state,
+    )?;
 
     terminal.draw(|frame| {
         ui::render(&mut app, &mut state, frame);
@@ -536,6 +689,28 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     assert!(state.focused);
     assert!(app.running);
 
+    // Focus the chart from facet values, the cursor shows values of the facets chart
+    let facet_labels = app.line_chart.data[0].clone();
+    for code in [KeyCode::Tab, KeyCode::Right] {
+        handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut app,
+            &mut state,
+        )?;
+        terminal.draw(|frame| {
+            ui::render(&mut app, &mut state, frame);
+        })?;
+    }
+    assert!(app.line_chart.focused() && app.facets_chart);
+    assert!(app.line_chart.cursor.is_some());
+    assert_eq!(app.line_chart.data[0], facet_labels);
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+        &mut app,
+        &mut state,
+    )?;
+    assert!(app.facet_values.focused());
+
     // Unfocus all widgets
     handle_events(
         Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty())),
@@ -549,7 +724,7 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
 
     println!("{:?}", terminal.backend().buffer());
     let buffer_str = buffer_view(terminal.backend().buffer());
-    assert!(!buffer_str.contains("Up/ Down [↑↓]"));
+    assert!(!buffer_str.contains("History [↑↓]"));
     assert!(!app.facet_values.focused());
     assert!(!state.focused);
     assert!(app.running);
@@ -565,6 +740,659 @@ fn launch_app_and_make_few_searches() -> AppResult<()> {
     Ok(())
 }
 
+#[test]
+fn parse_search_responses() -> AppResult<()> {
+    // Numeric facet values are converted to string
+    let response = parse_search_response(
+        r#"{"total": 30, "matches": [{"month": "2023-01", "count": 10}, {"month": "2023-02", "count": 20}],
+        "facets": {"port": [{"key": "2023-01", "values": [{"value": 443, "count": 10}]},
+        {"key": "2023-02", "values": [{"value": 443, "count": 15}, {"value": 80, "count": 5}]}]}}"#,
+    )?;
+    assert_eq!(response.facets["port"][1].values[1].value, "80");
+
+    let chart = Chart::from_response("query=nginx&facets=port", "port:2", &response)?;
+    assert_eq!(chart.x_labels, vec!["Jan 2023", "Feb 2023"]);
+    assert_eq!(chart.y_bounds, vec![0.0, 20.0]);
+
+    let facets = &chart.facets[0];
+    assert_eq!(facets.name, "port");
+    assert_eq!(facets.datasets[0].label, "443");
+    assert_eq!(facets.datasets[1].data, vec![(0.0, 0.0), (1.0, 5.0)]);
+
+    // Share of each facet value in the month
+    let shares = facets.shares();
+    assert_eq!(shares[0].data, vec![(0.0, 100.0), (1.0, 75.0)]);
+    assert_eq!(shares[1].data, vec![(0.0, 0.0), (1.0, 25.0)]);
+
+    // Each requested facet has its own chart in requested order
+    let response = parse_search_response(
+        r#"{"total": 10, "matches": [{"month": "2023-01", "count": 10}],
+        "facets": {"country": [{"key": "2023-01", "values": [{"value": "US", "count": 6}]}],
+        "org": [{"key": "2023-01", "values": [{"value": "OVH SAS", "count": 4}]}]}}"#,
+    )?;
+    let chart = Chart::from_response("", "org:10, country:10", &response)?;
+    let names: Vec<&str> = chart
+        .facets
+        .iter()
+        .map(|chart| chart.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["org", "country"]);
+    assert_eq!(chart.facets[1].datasets[0].label, "US");
+
+    // Malformed responses are errors instead of panics
+    assert!(matches!(
+        parse_search_response(r#"{"matches": []}"#),
+        Err(ApiError::Parse(_))
+    ));
+    assert!(Chart::from_response("", "org,asn", &response).is_err());
+
+    let response =
+        parse_search_response(r#"{"total": 1, "matches": [{"month": "2023-13", "count": 1}]}"#)?;
+    assert!(Chart::from_response("", "", &response).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn filter_months_range() -> AppResult<()> {
+    assert_eq!(DateRange::parse("")?, DateRange::default());
+    assert_eq!(
+        DateRange::parse(" 2023-02.. ")?,
+        DateRange::new(Some("2023-02".into()), None)
+    );
+    assert_eq!(
+        DateRange::parse("..2023-02")?.to_string(),
+        "..2023-02".to_string()
+    );
+    assert!(DateRange::parse("2023-2..").is_err());
+    assert!(DateRange::parse("2023-13").is_err());
+    assert!(DateRange::parse("2023-03..2023-01").is_err());
+
+    let response = parse_search_response(
+        r#"{"total": 60, "matches": [{"month": "2023-01", "count": 10}, {"month": "2023-02", "count": 20},
+        {"month": "2023-03", "count": 30}],
+        "facets": {"port": [{"key": "2023-01", "values": [{"value": 443, "count": 10}]},
+        {"key": "2023-02", "values": [{"value": 443, "count": 15}]},
+        {"key": "2023-03", "values": [{"value": 80, "count": 5}]}]}}"#,
+    )?;
+    let range = DateRange::parse("2023-02..2023-02")?;
+    let chart = Chart::from_response("", "port", &range.filter(&response))?;
+    assert_eq!(chart.x_labels, vec!["Feb 2023"]);
+    assert_eq!(chart.months, vec!["2023-02"]);
+    assert_eq!(chart.facets[0].datasets.len(), 1);
+    assert_eq!(range.filter(&response).facets["port"][0].month, "2023-02");
+    assert_eq!(range.filter(&response).total, 20);
+    assert_eq!(DateRange::default().filter(&response).total, 60);
+
+    // Saved query label carries the range
+    let encoded_query = encode_search("nginx", "port", &range);
+    assert_eq!(
+        encoded_query,
+        "query=nginx&facets=port&since=2023-02&until=2023-02"
+    );
+    assert_eq!(decode_range(&encoded_query), range);
+    assert_eq!(
+        encode_search("nginx", "", &DateRange::default()),
+        encode_query("nginx", "")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn summarize_query_stats() -> AppResult<()> {
+    let response = parse_search_response(
+        r#"{"total": 75, "matches": [{"month": "2022-01", "count": 10}, {"month": "2022-07", "count": 5},
+        {"month": "2023-01", "count": 40}, {"month": "2023-02", "count": 20}],
+        "facets": {"os": [{"key": "2022-01", "values": [{"value": "Linux", "count": 8}, {"value": "Windows", "count": 2}]},
+        {"key": "2022-07", "values": [{"value": "Linux", "count": 4}, {"value": "Windows", "count": 1}]},
+        {"key": "2023-01", "values": [{"value": "Linux", "count": 30}, {"value": "Windows", "count": 10}]},
+        {"key": "2023-02", "values": [{"value": "Linux", "count": 10}, {"value": "Windows", "count": 10}]}]}}"#,
+    )?;
+    let chart = Chart::from_response("query=nginx&facets=os", "os", &response)?;
+
+    let stats =
+        Stats::new(&chart, chart.facets.first(), "Jan 2022", "Jan 2023").expect("Missing stats");
+    assert_eq!(stats.latest, ("Jan 2023".to_string(), 40.0));
+    assert_eq!(stats.min, ("Jul 2022".to_string(), 5.0));
+    assert_eq!(stats.max, ("Jan 2023".to_string(), 40.0));
+    assert_eq!((stats.change, stats.change_percent), (30.0, Some(300.0)));
+    // Annualized over calendar months, not months of data
+    assert_eq!(stats.cagr.map(|cagr| cagr.round()), Some(300.0));
+
+    // Largest absolute gain is Linux, largest relative gain is Windows
+    let movers: Vec<(&str, String)> = stats
+        .movers
+        .iter()
+        .map(|mover| (mover.label.as_str(), mover.value()))
+        .collect();
+    assert_eq!(
+        movers,
+        vec![
+            ("Linux", "+22".to_string()),
+            ("Windows", "+400.0%".to_string())
+        ]
+    );
+
+    // All months if labels aren't in the chart
+    let stats = Stats::new(&chart, None, "", "").expect("Missing stats");
+    assert_eq!(stats.latest, ("Feb 2023".to_string(), 20.0));
+    assert!(stats.movers.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn format_chart_exports() -> AppResult<()> {
+    let rows: Vec<Vec<String>> = vec![
+        vec![
+            "Month".into(),
+            "query=nginx".into(),
+            "Amazon.com, Inc.".into(),
+        ],
+        vec!["Jun 2017".into(), "10".into(), "".into()],
+        vec!["Jul 2017".into(), "12.5".into(), "3".into()],
+    ];
+
+    assert_eq!(
+        format_rows(&rows, ExportFormat::Csv),
+        "Month,query=nginx,\"Amazon.com, Inc.\"\nJun 2017,10,\nJul 2017,12.5,3\n"
+    );
+    assert_eq!(
+        format_rows(&rows, ExportFormat::Tsv),
+        "Month\tquery=nginx\tAmazon.com, Inc.\nJun 2017\t10\t\nJul 2017\t12.5\t3\n"
+    );
+    assert_eq!(
+        format_rows(&rows, ExportFormat::Markdown),
+        "| Month | query=nginx | Amazon.com, Inc. |\n| --- | --- | --- |\n\
+        | Jun 2017 | 10 |  |\n| Jul 2017 | 12.5 | 3 |\n"
+    );
+
+    // Empty values are nulls
+    let json: serde_json::Value = serde_json::from_str(&format_rows(&rows, ExportFormat::Json))?;
+    assert_eq!(json["months"], serde_json::json!(["Jun 2017", "Jul 2017"]));
+    assert_eq!(json["lines"][0]["values"], serde_json::json!([10, 12.5]));
+    assert_eq!(json["lines"][1]["label"], "Amazon.com, Inc.");
+    assert_eq!(json["lines"][1]["values"], serde_json::json!([null, 3]));
+
+    // Timestamped file of the format
+    let time = std::time::UNIX_EPOCH + Duration::from_secs(1_690_882_200);
+    assert_eq!(
+        default_path(".", ExportFormat::Markdown, time),
+        "./strend-20230801-093000.md"
+    );
+    assert_eq!(
+        ExportFormat::Csv.next(true),
+        ExportFormat::ALL[ExportFormat::ALL.len() - 1]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn render_chart_image() -> AppResult<()> {
+    let mut image = ChartImage {
+        title: "nginx by org".to_string(),
+        y_title: "% share".to_string(),
+        x_bounds: [0.0, 2.0],
+        x_ticks: vec!["Jun 2017".into(), "Jul 2017".into(), "Aug 2017".into()],
+        x_labels: vec!["Jun 2017".into(), "Jul 2017".into(), "Aug 2017".into()],
+        y_bounds: [0.0, 100.0],
+        y_ticks: vec!["0%".into(), "50%".into(), "100%".into()],
+        stacked: false,
+        dashed: false,
+        lines: vec![
+            ImageLine {
+                label: "Amazon <AWS> & Co".to_string(),
+                color: Color::Rgb(213, 5, 39),
+                points: vec![(0.0, 50.0), (1.0, 25.0), (2.0, 0.0)],
+            },
+            ImageLine {
+                label: "Google".to_string(),
+                color: Color::Rgb(21, 137, 64),
+                points: vec![(0.0, 100.0), (1.0, 75.0), (2.0, 50.0)],
+            },
+        ],
+    };
+
+    // Lines from the left bottom to the right top of the plot
+    let svg = image.svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains(">nginx by org</text>"));
+    assert!(svg.contains(">% share</text>"));
+    assert!(svg.contains(">Amazon &lt;AWS&gt; &amp; Co</text>"));
+    assert!(svg.contains(
+        "<polyline points=\"90.0,260.0 625.0,360.0 1160.0,460.0\" fill=\"none\" stroke=\"#d50527\""
+    ));
+    assert!(svg.contains("<title>Jul 2017: 75</title>"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    // Stacked areas are filled down to the previous line
+    image.stacked = true;
+    let svg = image.svg();
+    assert!(svg.contains(
+        "<polygon points=\"90.0,260.0 625.0,360.0 1160.0,460.0 1160.0,460.0 625.0,460.0 90.0,460.0\" fill=\"#d50527\""
+    ));
+    assert!(svg.contains(
+        "<polygon points=\"90.0,60.0 625.0,160.0 1160.0,260.0 1160.0,460.0 625.0,360.0 90.0,260.0\" fill=\"#158940\""
+    ));
+
+    #[cfg(feature = "png")]
+    assert!(strend::image::png(&svg)?.starts_with(b"\x89PNG"));
+
+    // Lines without colors are dashed, stacked areas are lighter
+    for line in image.lines.iter_mut() {
+        line.color = Color::Reset;
+    }
+    image.dashed = true;
+    assert!(image.svg().contains("fill=\"black\" fill-opacity=\"0.65\""));
+    image.stacked = false;
+    let svg = image.svg();
+    assert!(svg.contains("stroke=\"black\" stroke-width=\"2\" stroke-linejoin=\"round\"/>"));
+    assert!(svg.contains("stroke-linejoin=\"round\" stroke-dasharray=\"8 4\"/>"));
+
+    Ok(())
+}
+
+#[test]
+fn apply_themes() -> AppResult<()> {
+    let config = Config::parse("theme = \"colorblind\"")?;
+    assert_eq!(config.theme, Theme::Colorblind);
+    assert_eq!(config.line_colors()[0], Color::Rgb(230, 159, 0));
+    // Colors of the config replace colors of the theme
+    let config = Config::parse("theme = \"high_contrast\"\ncolors = [\"#d50527\"]")?;
+    assert_eq!(config.line_colors(), vec![Color::Rgb(213, 5, 39)]);
+    assert!(Config::parse("theme = \"dark\"").is_err());
+
+    // Monochrome lines have distinct markers, dimmed after all markers are used
+    let styles = Theme::Monochrome.line_styles(&[]);
+    assert!(styles.iter().all(|style| style.color == Color::Reset));
+    assert_ne!(styles[0].marker, styles[1].marker);
+    assert_eq!(styles[0].marker, styles[4].marker);
+    assert_ne!(styles[0].modifier, styles[4].modifier);
+    assert_eq!(Theme::Default.line_styles(&[]).len(), ui::LINE_COLORS.len());
+
+    let no_color = |value: &str| {
+        Config::default().with_vars(vec![("NO_COLOR".to_string(), value.to_string())])
+    };
+    assert_eq!(no_color("1")?.theme, Theme::Monochrome);
+    assert_eq!(no_color("")?.theme, Theme::Default);
+    assert_eq!(
+        Config::parse("theme = \"colorblind\"")?
+            .with_vars(vec![("STREND_THEME".to_string(), "monochrome".to_string())])?
+            .theme,
+        Theme::Monochrome
+    );
+
+    // Saved queries are marked by the markers of their lines
+    let (_, receiver) = mpsc::channel();
+    let client = TrendsClient::new("http://127.0.0.1:9", "key");
+    let mut app = App::with_client("nginx".to_string(), String::new(), receiver, client);
+    app.configure(no_color("1")?);
+    let mut state = input_state();
+    let response = parse_search_response(
+        r#"{"total": 30, "matches": [{"month": "2023-01", "count": 10}, {"month": "2023-02", "count": 20}]}"#,
+    )?;
+    for query in ["query=apache&facets=", "query=nginx&facets="] {
+        app.charts.insert(
+            query.to_string(),
+            Chart::from_response(query, "", &response)?,
+        );
+        app.queries.push(query.to_string());
+    }
+    let mut terminal = Terminal::new(TestBackend::new(140, 40))?;
+    terminal.draw(|frame| ui::render(&mut app, &mut state, frame))?;
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer
+        .content
+        .iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(text.contains("⣿ query=nginx"));
+    assert!(text.contains("• query=apache"));
+    assert!(buffer.content.iter().all(|cell| cell.fg == Color::Reset));
+
+    Ok(())
+}
+
+#[test]
+fn parse_config() -> AppResult<()> {
+    assert_eq!(Config::parse("")?, Config::default());
+
+    let text = r##"
+tick_rate = 100
+max_saved_queries = 10
+facets = "country:10"
+export_dir = "/tmp/exports"
+colors = ["#d50527", "#158940"]
+"##;
+    let config = Config::parse(text)?;
+    assert_eq!(config.tick_rate, 100);
+    assert_eq!(config.max_saved_queries, 10);
+    assert_eq!(config.selected_facet_lines, 5);
+    assert_eq!(config.facets, "country:10");
+    assert_eq!(config.export_dir, "/tmp/exports");
+    assert_eq!(
+        config.line_colors(),
+        vec![Color::Rgb(213, 5, 39), Color::Rgb(21, 137, 64)]
+    );
+
+    // Environment variables override the file
+    let vars = |vars: &[(&str, &str)]| {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<(String, String)>>()
+    };
+    let config = config.with_vars(vars(&[
+        ("STREND_TICK_RATE", "50"),
+        ("STREND_PROXY", "socks5://localhost:9050"),
+        ("STREND_FACETS", "2022"),
+        ("STREND_CONFIG", "/tmp/config.toml"),
+        ("TICK_RATE", "1"),
+    ]))?;
+    assert_eq!(config.tick_rate, 50);
+    assert_eq!(config.proxy, "socks5://localhost:9050");
+    assert_eq!(config.facets, "2022");
+    assert_eq!(config.max_saved_queries, 10);
+
+    // Flags override both, e.g. --export-dir
+    let config = config.with_export_dir("~/exports");
+    assert!(config.export_dir.ends_with("/exports") && !config.export_dir.starts_with('~'));
+
+    assert_eq!(
+        Config::parse("\ntick_rate = \"fast\"").unwrap_err(),
+        "line 2, invalid type: string \"fast\", expected u64"
+    );
+    assert!(Config::parse("tick_rate = 0").is_err());
+    assert!(Config::parse("colors = [\"red\"]").is_err());
+    assert!(Config::parse("unknown = 1")
+        .unwrap_err()
+        .contains("unknown field `unknown`"));
+    assert_eq!(
+        Config::default()
+            .with_vars(vars(&[("STREND_API_TIMEOUT", "0")]))
+            .unwrap_err(),
+        "Invalid STREND_API_TIMEOUT (api_timeout must be greater than 0)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn remap_keys() -> AppResult<()> {
+    let key = |code, modifiers| KeyEvent::new(code, modifiers);
+    let keymap = Keymap::default();
+    assert!(keymap.matches(
+        Action::Quit,
+        &key(KeyCode::Char('C'), KeyModifiers::CONTROL)
+    ));
+    assert!(keymap.matches(
+        Action::YScale,
+        &key(KeyCode::Char('S'), KeyModifiers::SHIFT)
+    ));
+    assert!(keymap.matches(
+        Action::SwitchPanelBack,
+        &key(KeyCode::BackTab, KeyModifiers::SHIFT)
+    ));
+    assert!(!keymap.matches(
+        Action::Export,
+        &key(KeyCode::Char('e'), KeyModifiers::empty())
+    ));
+    assert_eq!(
+        keymap.help(
+            "Zoom",
+            &[Action::ZoomIn, Action::ZoomOut, Action::ResetZoom]
+        ),
+        Some("Zoom [+-0]".to_string())
+    );
+
+    let chord = KeyChord::parse("ctrl+shift+left")?;
+    assert_eq!(chord.label(), "^⇧←");
+    assert_eq!(chord.to_string(), "ctrl+shift+left");
+    assert_eq!(KeyChord::parse("ctrl++")?.code, KeyCode::Char('+'));
+    assert_eq!(KeyChord::parse("F2")?.code, KeyCode::F(2));
+    assert!(KeyChord::parse("hyper+x").is_err());
+
+    let config = Config::parse(
+        r#"
+[keys]
+export = "ctrl+x"
+stats = ["i", "f2"]
+zoom_range = []
+"#,
+    )?;
+    let keymap = config.keymap();
+    assert!(keymap.matches(
+        Action::Export,
+        &key(KeyCode::Char('x'), KeyModifiers::CONTROL)
+    ));
+    assert!(!keymap.matches(
+        Action::Export,
+        &key(KeyCode::Char('e'), KeyModifiers::CONTROL)
+    ));
+    assert_eq!(
+        keymap.help("Stats", &[Action::Stats]),
+        Some("Stats [i]".to_string())
+    );
+    assert_eq!(keymap.help("Zoom range", &[Action::ZoomRange]), None);
+    assert_eq!(
+        keymap.chords(Action::Quit),
+        Keymap::default().chords(Action::Quit)
+    );
+
+    let config = Config::default().with_vars(vec![(
+        "STREND_KEYS".to_string(),
+        "{ quit = \"ctrl+q\" }".to_string(),
+    )])?;
+    assert_eq!(
+        config.keymap().chords(Action::Quit),
+        [KeyChord::parse("ctrl+q")?]
+    );
+
+    // Keys of other panels can be shared, but not with global keys
+    assert!(Config::parse("[keys]\nstats = \"enter\"").is_err());
+    assert!(Config::parse("[keys]\ntoggle = \"s\"").is_ok());
+    assert_eq!(
+        Config::parse("[keys]\nshare = \"tab\"").unwrap_err(),
+        "Key tab is bound to both switch_panel and share"
+    );
+    // Global keys are checked while typing in inputs
+    assert_eq!(
+        Config::parse("[keys]\nquit = \"q\"").unwrap_err(),
+        "Key q of quit is typed in inputs, use it with ctrl or alt"
+    );
+    assert!(Config::parse("[keys]\nswitch_panel = \"shift+n\"").is_err());
+    assert!(Config::parse("[keys]\nsearch = \"space\"").is_err());
+    assert!(Config::parse("[keys]\nquit = [\"ctrl+c\", \"alt+q\"]").is_ok());
+    // Export dialog confirms with its own keys
+    let keymap = Config::parse("[keys]\nconfirm = \"ctrl+s\"")?.keymap();
+    assert_eq!(
+        keymap.action(
+            Context::Dialog,
+            &key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        ),
+        Some(Action::Confirm)
+    );
+    assert_eq!(
+        keymap.action(Context::Dialog, &key(KeyCode::Enter, KeyModifiers::empty())),
+        None
+    );
+    assert_eq!(
+        Config::parse("[keys]\nconfirm = \"tab\"").unwrap_err(),
+        "Key tab of confirm is used by the export dialog"
+    );
+    assert!(Config::parse("[keys]\nconfirm = \"left\"").is_err());
+
+    // Keys handled by panels themselves can't be remapped
+    assert_eq!(
+        Config::parse("[keys]\nzoom_in = \"left\"").unwrap_err(),
+        "Key left of zoom_in is used by the chart"
+    );
+    assert_eq!(
+        Config::parse("[keys]\nselect_all = \"down\"").unwrap_err(),
+        "Key down of select_all is used by lists"
+    );
+    assert!(Config::parse("[keys]\nunfocus = \"enter\"").is_err());
+    assert!(Config::parse("[keys]\nswitch_panel = \"ctrl+a\"").is_err());
+    assert!(Config::parse("[keys]\nzoom_in = \"up\"").is_ok());
+    assert!(Config::parse("[keys]\nexport = \"ctrl+\"")
+        .unwrap_err()
+        .contains("of export"));
+    assert!(Config::parse("[keys]\nfly = \"f\"")
+        .unwrap_err()
+        .contains("unknown variant `fly`"));
+
+    Ok(())
+}
+
+#[test]
+fn format_headless_search() -> AppResult<()> {
+    let response = parse_search_response(
+        r#"{"total": 30, "matches": [{"month": "2023-01", "count": 10}, {"month": "2023-02", "count": 20}],
+        "facets": {"org": [{"key": "2023-01", "values": [{"value": "Amazon.com, Inc.", "count": 7}]},
+        {"key": "2023-02", "values": [{"value": "Amazon.com, Inc.", "count": 9}, {"value": "OVH SAS", "count": 11}]}]}}"#,
+    )?;
+
+    let csv = format_search("nginx", "org:2", &response, OutputFormat::Csv);
+    assert_eq!(
+        csv,
+        "Month,Total,\"org:Amazon.com, Inc.\",org:OVH SAS\n2023-01,10,7,0\n2023-02,20,9,11\n"
+    );
+
+    let table = format_search("nginx", "org:2", &response, OutputFormat::Table);
+    assert!(table.contains("Facets: org:2"));
+    assert!(table.contains("2023-02  20     9                     11"));
+
+    let json: serde_json::Value = serde_json::from_str(&format_search(
+        "nginx",
+        "org:2",
+        &response,
+        OutputFormat::Json,
+    ))?;
+    assert_eq!(
+        json["facet_buckets"]["org"][1]["values"][1]["value"],
+        "OVH SAS"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn plot_chart_without_tui() -> AppResult<()> {
+    let response = parse_search_response(
+        r#"{"total": 60, "matches": [{"month": "2023-01", "count": 10}, {"month": "2023-02", "count": 20}, {"month": "2023-03", "count": 30}]}"#,
+    )?;
+    let chart = Chart::from_response("nginx", "", &response)?;
+
+    let output = plot("Query: nginx", &chart, 5, 40, 10, PlotMarker::Ascii, &[]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 12);
+    assert_eq!(lines[0], "Query: nginx");
+    assert!(lines[1].trim_start().starts_with("30|") && lines[1].ends_with('*'));
+    assert!(lines[9].trim_start().starts_with("+---"));
+    assert!(lines[10].starts_with("Jan 2023"));
+    assert_eq!(lines[11], "* nginx (60)");
+    assert!(output.contains('*') && !output.contains('\u{1b}'));
+
+    // Smallest size of --width and --height
+    let output = plot("Query: nginx", &chart, 5, 1, 1, PlotMarker::Ascii, &[]);
+    assert!(output.starts_with("Query: nginx\n"));
+
+    Ok(())
+}
+
+#[test]
+fn cache_search_responses() -> AppResult<()> {
+    let dir = env::temp_dir().join(format!("strend-cache-{}", std::process::id()));
+    let response =
+        parse_search_response(r#"{"total": 1, "matches": [{"month": "2023-01", "count": 1}]}"#)?;
+    let key = "query=nginx&facets=os%3A5";
+
+    let cache = ResponseCache::new(CacheMode::Enabled).with_dir(dir.clone());
+    assert!(cache.get(key).is_none());
+    cache.set(key, &response)?;
+    assert_eq!(cache.get(key), Some(response.clone()));
+    assert!(cache.get("query=nginx&facets=").is_none());
+
+    // Expired
+    let expired = cache.clone().with_ttl(Duration::from_secs(0));
+    sleep(Duration::from_millis(1100));
+    assert!(expired.get(key).is_none());
+
+    // Refresh mode ignores cached responses
+    let refresh = ResponseCache::new(CacheMode::Refresh).with_dir(dir.clone());
+    assert!(refresh.get(key).is_none());
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn save_and_restore_sessions() -> AppResult<()> {
+    let dir = env::temp_dir().join(format!("strend-sessions-{}", std::process::id()));
+    let store = SessionStore::new().with_dir(dir.clone());
+    assert_eq!(store.load("work")?, None);
+    assert!(store.list()?.is_empty());
+
+    let session = Session {
+        queries: vec!["query=nginx&facets=".to_string()],
+        selected_queries: vec!["query=nginx&facets=".to_string()],
+        highlighted_query: Some("query=nginx&facets=".to_string()),
+        ..Default::default()
+    };
+    store.save("work", &session)?;
+    store.save("home_2", &Session::default())?;
+    assert_eq!(store.load("work")?, Some(session));
+    assert_eq!(store.list()?, vec!["home_2", "work"]);
+
+    // Names can't point outside sessions directory
+    assert!(store.save("../work", &Session::default()).is_err());
+    assert!(store.load("").is_err());
+
+    store.delete("home_2")?;
+    assert_eq!(store.list()?, vec!["work"]);
+
+    // Queries are searched in the background, from the cache here
+    let response =
+        parse_search_response(r#"{"total": 1, "matches": [{"month": "2023-01", "count": 1}]}"#)?;
+    let cache = ResponseCache::new(CacheMode::Enabled).with_dir(dir.join("cache"));
+    cache.set("query=nginx&facets=", &response)?;
+    let (_, receiver) = mpsc::channel();
+    let client = TrendsClient::new("http://127.0.0.1:9", "key");
+    let mut app = App::with_client(String::new(), String::new(), receiver, client);
+    app.cache = cache;
+    let mut state = input_state();
+    app.restore_session(store.load("work")?.unwrap());
+    assert!(app.blocking > 0);
+    while app.blocking > 0 {
+        app.receive_session(&mut state);
+    }
+    assert_eq!(app.queries, ["query=nginx&facets="]);
+    assert!(app.searched.contains_key("query=nginx&facets="));
+    assert_eq!(app.saved_queries.state.selected_indexes(), &[0]);
+    assert!(state.app_log.is_empty());
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+// Type the file in the export dialog and press Enter
+fn export_to(app: &mut App, state: &mut AppState, path: &str) -> AppResult<()> {
+    app.export_dialog.path.set_input("");
+    for c in path.chars() {
+        handle_events(
+            Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty())),
+            app,
+            state,
+        )?;
+    }
+    handle_events(
+        Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty())),
+        app,
+        state,
+    )
+}
+
 fn search_and_render(
     app: &mut App,
     state: &mut AppState,
@@ -614,3 +1442,648 @@ fn buffer_view(buffer: &Buffer) -> String {
     }
     view
 }
+
+#[test]
+fn navigate_and_search_input_history() -> AppResult<()> {
+    let path = env::temp_dir().join(format!("strend-history-{}/queries", std::process::id()));
+    let mut state = input_state();
+    let mut press = |input: &mut UserInput, code: KeyCode, modifiers: KeyModifiers| {
+        input.handle_events(Event::Key(KeyEvent::new(code, modifiers)), &mut state);
+    };
+
+    let mut input = UserInput::new(String::new());
+    input.load_history(path.clone());
+    for query in ["nginx", "apache", "nginx port:443", "nginx port:443", " "] {
+        input.set_input(query);
+        input.submit_message();
+    }
+    assert_eq!(input.messages(), ["nginx", "apache", "nginx port:443"]);
+
+    // Up/ Down keys
+    input.set_input("draft");
+    assert!(input.previous_message());
+    assert!(input.previous_message());
+    assert_eq!(input.get_input(), "apache");
+    assert!(input.next_message());
+    assert!(input.next_message());
+    assert_eq!(input.get_input(), "draft");
+    assert!(!input.next_message());
+
+    // Ctrl+P/ Ctrl+N
+    press(&mut input, KeyCode::Char('p'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "nginx port:443");
+    press(&mut input, KeyCode::Char('n'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "draft");
+
+    // Ctrl+R
+    press(&mut input, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    for c in "ngi".chars() {
+        press(&mut input, KeyCode::Char(c), KeyModifiers::empty());
+    }
+    assert_eq!(input.history_search(), Some(("ngi", true)));
+    assert_eq!(input.get_input(), "nginx port:443");
+    press(&mut input, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "nginx");
+    // No older matches
+    press(&mut input, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "nginx");
+    press(&mut input, KeyCode::Char('z'), KeyModifiers::empty());
+    assert_eq!(input.history_search(), Some(("ngiz", false)));
+    // Cancel
+    press(&mut input, KeyCode::Char('g'), KeyModifiers::CONTROL);
+    assert_eq!(input.history_search(), None);
+    assert_eq!(input.get_input(), "draft");
+
+    // Accept matched message
+    press(&mut input, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    press(&mut input, KeyCode::Char('a'), KeyModifiers::empty());
+    press(&mut input, KeyCode::Left, KeyModifiers::empty());
+    assert_eq!(input.history_search(), None);
+    assert_eq!(input.get_input(), "apache");
+
+    // Persisted across runs
+    let mut input = UserInput::new(String::new());
+    input.load_history(path.clone());
+    assert_eq!(input.messages(), ["nginx", "apache", "nginx port:443"]);
+
+    // Esc cancels searching history of the focused searchbox, again unfocuses it
+    let (_, receiver) = mpsc::channel();
+    let client = TrendsClient::new("http://127.0.0.1:9", "key");
+    let mut app = App::with_client(String::new(), String::new(), receiver, client);
+    app.search_input.load_history(path.clone());
+    app.search_input.set_focus(true);
+    let mut state = input_state();
+    let key = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+    handle_events(
+        key(KeyCode::Char('r'), KeyModifiers::CONTROL),
+        &mut app,
+        &mut state,
+    )?;
+    handle_events(
+        key(KeyCode::Esc, KeyModifiers::empty()),
+        &mut app,
+        &mut state,
+    )?;
+    assert!(state.focused && app.search_input.history_search().is_none());
+    handle_events(
+        key(KeyCode::Esc, KeyModifiers::empty()),
+        &mut app,
+        &mut state,
+    )?;
+    assert!(!state.focused);
+
+    std::fs::remove_dir_all(path.parent().unwrap())?;
+    Ok(())
+}
+
+#[test]
+fn edit_input_with_readline_keys() -> AppResult<()> {
+    let mut state = input_state();
+    let mut press = |input: &mut UserInput, code: KeyCode, modifiers: KeyModifiers| {
+        input.handle_events(Event::Key(KeyEvent::new(code, modifiers)), &mut state);
+    };
+    let mut input = UserInput::new("product:nginx port:443 country:DE".to_string());
+
+    press(&mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "product:nginx port:443 ");
+    press(&mut input, KeyCode::Char('b'), KeyModifiers::ALT);
+    assert_eq!(input.cursor_position, 19);
+    press(&mut input, KeyCode::Char('b'), KeyModifiers::ALT);
+    press(&mut input, KeyCode::Char('f'), KeyModifiers::ALT);
+    assert_eq!(input.cursor_position, 18);
+    press(&mut input, KeyCode::Char('k'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "product:nginx port");
+    press(&mut input, KeyCode::Home, KeyModifiers::empty());
+    press(&mut input, KeyCode::Delete, KeyModifiers::empty());
+    assert_eq!(input.get_input(), "roduct:nginx port");
+    press(&mut input, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    assert_eq!(input.cursor_position, 17);
+    press(&mut input, KeyCode::Left, KeyModifiers::empty());
+    press(&mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+    assert_eq!(input.get_input(), "t");
+    assert_eq!(input.cursor_position, 0);
+
+    // Keep cursor visible in a narrow input box
+    input.set_input("product:nginx port:443");
+    input.scroll(10);
+    assert_eq!(input.visible_input(), " port:443");
+    assert_eq!(input.cursor_column(), 9);
+    press(&mut input, KeyCode::Char('a'), KeyModifiers::CONTROL);
+    input.scroll(10);
+    assert_eq!(input.cursor_column(), 0);
+    assert_eq!(input.visible_input(), "product:nginx port:443");
+    press(&mut input, KeyCode::End, KeyModifiers::empty());
+    press(&mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+    input.scroll(10);
+    assert_eq!(input.visible_input(), "");
+
+    Ok(())
+}
+
+#[test]
+fn edit_unicode_input() -> AppResult<()> {
+    let mut state = input_state();
+    let mut press = |input: &mut UserInput, code: KeyCode| {
+        input.handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut state,
+        );
+    };
+    let mut input = UserInput::new(String::new());
+
+    for c in "org:\"Telefónica\" 東京".chars() {
+        press(&mut input, KeyCode::Char(c));
+    }
+    assert_eq!(input.cursor_position, 19);
+    // Wide characters take 2 columns
+    assert_eq!(input.cursor_column(), 21);
+
+    // Combining accent joins previous letter
+    press(&mut input, KeyCode::Home);
+    press(&mut input, KeyCode::Char('e'));
+    press(&mut input, KeyCode::Char('\u{301}'));
+    assert_eq!(input.cursor_position, 1);
+    press(&mut input, KeyCode::Backspace);
+    assert_eq!(input.get_input(), "org:\"Telefónica\" 東京");
+
+    press(&mut input, KeyCode::End);
+    press(&mut input, KeyCode::Left);
+    press(&mut input, KeyCode::Backspace);
+    press(&mut input, KeyCode::Delete);
+    assert_eq!(input.get_input(), "org:\"Telefónica\" ");
+    for _ in 0..6 {
+        press(&mut input, KeyCode::Left);
+    }
+    press(&mut input, KeyCode::Backspace);
+    assert_eq!(input.get_input(), "org:\"Telefnica\" ");
+
+    // Never split wide characters on scrolling
+    input.set_input("東京東京");
+    input.scroll(5);
+    assert_eq!(input.visible_input(), "東京");
+    assert_eq!(input.cursor_column(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn tokenize_and_check_queries() -> AppResult<()> {
+    let filters = Completions::default().filters;
+    let check_query = |query| check_query(query, &filters);
+    let query = r#"nginx -port:22 org:"Amazon.com, Inc." prot:80"#;
+    let tokens: Vec<(TokenKind, &str)> = tokenize(query, &filters)
+        .iter()
+        .map(|token| (token.kind, &query[token.start..token.end]))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenKind::Text, "nginx"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Negation, "-"),
+            (TokenKind::Filter, "port:"),
+            (TokenKind::Value, "22"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Filter, "org:"),
+            (TokenKind::QuotedValue, r#""Amazon.com, Inc.""#),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::UnknownFilter, "prot:"),
+            (TokenKind::Value, "80"),
+        ]
+    );
+
+    assert_eq!(check_query(query), Some("Unknown filter: prot".to_string()));
+    assert_eq!(
+        check_query("ssl.cert.subject.cn:example.com -port:22"),
+        None
+    );
+    assert_eq!(check_query("ssl:example.com title:login html:admin"), None);
+    assert_eq!(
+        check_query("port: 22"),
+        Some("Missing value of filter: port:".to_string())
+    );
+    assert_eq!(
+        check_query(r#"org:"Amazon"#),
+        Some("Unclosed quote in query".to_string())
+    );
+
+    assert_eq!(check_facets(""), None);
+    assert_eq!(check_facets("os:5, country:10"), None);
+    assert_eq!(
+        check_facets("os:5,org"),
+        Some("Missing limit of facet: org, e.g. org:10".to_string())
+    );
+    assert_eq!(
+        check_facets("os:five"),
+        Some("Invalid limit of facet: os:five, e.g. os:10".to_string())
+    );
+    assert_eq!(check_facets("os:5,"), Some("Empty facet".to_string()));
+
+    Ok(())
+}
+
+#[test]
+fn complete_filter_and_facet_names() -> AppResult<()> {
+    let completions = Completions::default();
+    assert_eq!(
+        complete(&completions.filters, "ssl.cert.su"),
+        vec!["ssl.cert.subject.cn"]
+    );
+    assert_eq!(complete(&completions.facets, "title"), vec!["http.title"]);
+    assert!(complete(&completions.facets, "org").is_empty());
+
+    assert_eq!(CompletionKind::Filter.word_start("nginx -ssl.ce"), Some(7));
+    assert_eq!(CompletionKind::Filter.word_start("nginx port:4"), None);
+    assert_eq!(CompletionKind::Facet.word_start("os:5, coun"), Some(6));
+
+    let mut state = input_state();
+    let mut press = |input: &mut UserInput, code: KeyCode| {
+        input.handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut state,
+        );
+    };
+    let mut input = UserInput::new("nginx ".to_string())
+        .with_completions(CompletionKind::Filter, completions.filters.clone());
+    assert!(!input.can_complete());
+
+    // Complete the only match
+    for c in "ssl.cert.su".chars() {
+        press(&mut input, KeyCode::Char(c));
+    }
+    press(&mut input, KeyCode::Tab);
+    assert!(!input.completing());
+    assert_eq!(input.get_input(), "nginx ssl.cert.subject.cn:");
+
+    // Choose from popup
+    input.set_input("nginx -ssl.cert.");
+    press(&mut input, KeyCode::Tab);
+    let (items, index, _) = input.completion().unwrap();
+    assert_eq!((items.len(), items[index].as_str()), (9, "ssl.cert.alg"));
+    press(&mut input, KeyCode::Up);
+    assert_eq!(input.completion().unwrap().1, 8);
+    press(&mut input, KeyCode::Enter);
+    assert_eq!(input.get_input(), "nginx -ssl.cert.subject.cn:");
+    assert!(!input.completing());
+
+    // Filter items on typing
+    input.set_input("nginx ssl.cert.");
+    press(&mut input, KeyCode::Tab);
+    press(&mut input, KeyCode::Char('i'));
+    assert_eq!(input.completion().unwrap().0, ["ssl.cert.issuer.cn"]);
+    press(&mut input, KeyCode::Enter);
+    assert_eq!(input.get_input(), "nginx ssl.cert.issuer.cn:");
+
+    // Tab leaves names only part of other names, and names of the closed popup
+    input.set_input("nginx port");
+    assert!(!input.can_complete());
+    input.set_input("nginx ssl");
+    assert!(input.can_complete());
+    press(&mut input, KeyCode::Tab);
+    press(&mut input, KeyCode::Esc);
+    assert!(!input.completing() && !input.can_complete());
+    press(&mut input, KeyCode::Tab);
+    assert!(!input.completing());
+    press(&mut input, KeyCode::Char('.'));
+    assert!(input.can_complete());
+
+    // Fall back to built-in names if API is unreachable, otherwise use cached names
+    let dir = env::temp_dir().join(format!("strend-completions-{}", std::process::id()));
+    let path = dir.join("completions.json");
+    let client =
+        TrendsClient::new("http://127.0.0.1:9", "key").with_shodan_api_url("http://127.0.0.1:9");
+    assert_eq!(
+        Completions::load(&client, &path, CacheMode::Enabled),
+        completions
+    );
+
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, r#"{"filters": ["http.new_filter"], "facets": []}"#)?;
+    let loaded = Completions::load(&client, &path, CacheMode::Enabled);
+    assert!(loaded.filters.contains(&"http.new_filter".to_string()));
+    assert_eq!(loaded.facets, completions.facets);
+    assert_eq!(check_query("http.new_filter:1", &loaded.filters), None);
+    assert!(check_query("http.new_filter:1", &completions.filters).is_some());
+
+    // Cached names are still used if can't refresh, but not without cache
+    let loaded = Completions::load(&client, &path, CacheMode::Refresh);
+    assert!(loaded.filters.contains(&"http.new_filter".to_string()));
+    assert_eq!(
+        Completions::load(&client, &path, CacheMode::Disabled),
+        completions
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn click_and_scroll_with_mouse() -> AppResult<()> {
+    let mut state = input_state();
+    let mouse = |kind: MouseEventKind, column: u16, row: u16| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::empty(),
+        })
+    };
+    let click = |column: u16, row: u16| mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+
+    // Click toggles the item under the mouse, top border isn't an item
+    let mut list: MultiStatefulList<String> = MultiStatefulList::new();
+    list.set_items(vec!["nginx".into(), "apache".into(), "iis".into()]);
+    list.set_state_key(Some("query=nginx&facets=".to_string()));
+    list.set_area(Rect::new(0, 0, 20, 6));
+    list.handle_events(click(3, 0), &mut state);
+    assert_eq!(list.state.selected(), None);
+    list.handle_events(click(3, 2), &mut state);
+    assert_eq!(list.state.selected(), Some(1));
+    assert_eq!(list.state.selected_indexes(), &vec![1]);
+    assert_eq!(
+        state.facet_indexes["query=nginx&facets="].selected_indexes,
+        vec![1]
+    );
+
+    // Scrolling stops at the first and last items
+    for _ in 0..3 {
+        list.handle_events(mouse(MouseEventKind::ScrollDown, 3, 2), &mut state);
+    }
+    assert_eq!(list.state.selected(), Some(2));
+    list.handle_events(mouse(MouseEventKind::ScrollUp, 3, 2), &mut state);
+    assert_eq!(list.state.selected(), Some(1));
+
+    // Hovering the chart picks the month drawn at the column
+    let mut chart = LineChart::new();
+    chart.set_area(Rect::new(10, 0, 11, 5));
+    chart.set_x_bounds([0.0, 10.0]);
+    assert_eq!(chart.month_at(9), None);
+    assert_eq!(chart.month_at(10), Some(0));
+    assert_eq!(chart.month_at(20), Some(10));
+    assert_eq!(chart.column_of(5), 15);
+    chart.handle_events(mouse(MouseEventKind::Moved, 15, 2), &mut state);
+    assert_eq!(chart.cursor, Some(5));
+
+    // Click moves the input cursor, wide characters take two columns
+    let mut input = UserInput::new("日本 port:22".to_string());
+    input.set_area(Rect::new(5, 0, 20, 1));
+    input.handle_events(click(8, 0), &mut state);
+    assert_eq!(input.cursor_position, 1);
+    input.handle_events(click(24, 0), &mut state);
+    assert_eq!(input.cursor_position, 10);
+
+    // Click focuses the panel under the mouse
+    let (_, receiver) = mpsc::channel();
+    let client = TrendsClient::new("http://127.0.0.1:9", "key");
+    let mut app = App::with_client(String::new(), String::new(), receiver, client);
+    state.focused = false;
+    app.saved_queries.set_area(Rect::new(0, 4, 20, 10));
+    handle_events(click(5, 8), &mut app, &mut state)?;
+    assert!(!state.focused, "hidden panels can't be clicked");
+    app.saved_queries.set_hide(false);
+    handle_events(click(5, 20), &mut app, &mut state)?;
+    assert!(!state.focused);
+    handle_events(click(5, 8), &mut app, &mut state)?;
+    assert!(state.focused && app.saved_queries.focused());
+
+    Ok(())
+}
+
+#[test]
+fn move_chart_cursor() -> AppResult<()> {
+    let mut state = input_state();
+    let mut chart = LineChart::new();
+    let mut press = |chart: &mut LineChart, code: KeyCode| {
+        chart.handle_events(
+            Event::Key(KeyEvent::new(code, KeyModifiers::empty())),
+            &mut state,
+        );
+    };
+
+    // No data to inspect
+    press(&mut chart, KeyCode::Left);
+    assert_eq!(chart.cursor, None);
+
+    chart.data = vec![
+        vec!["Month".into(), "query=nginx&facets=".into()],
+        vec!["Jun 2017".into(), "19799459".into()],
+        vec!["Jul 2017".into(), "21077099".into()],
+        vec!["Aug 2017".into(), "21190581".into()],
+    ];
+    chart.set_focus(true);
+    press(&mut chart, KeyCode::Left);
+    assert_eq!(chart.cursor, Some(2));
+    press(&mut chart, KeyCode::Left);
+    press(&mut chart, KeyCode::Left);
+    press(&mut chart, KeyCode::Left);
+    assert_eq!(chart.cursor, Some(0));
+    press(&mut chart, KeyCode::Right);
+    assert_eq!(chart.cursor, Some(1));
+    press(&mut chart, KeyCode::End);
+    press(&mut chart, KeyCode::Right);
+    assert_eq!(chart.cursor, Some(2));
+    press(&mut chart, KeyCode::Home);
+    assert_eq!(chart.cursor, Some(0));
+
+    // Fewer months after searching other queries
+    chart.cursor = Some(10);
+    chart.data.truncate(3);
+    press(&mut chart, KeyCode::Right);
+    assert_eq!(chart.cursor, Some(1));
+
+    chart.set_focus(false);
+    assert_eq!(chart.cursor, None);
+
+    Ok(())
+}
+
+#[test]
+fn zoom_and_pan_chart() -> AppResult<()> {
+    let mut state = input_state();
+    let mut chart = LineChart::new();
+    let mut press = |chart: &mut LineChart, code: KeyCode, modifiers: KeyModifiers| {
+        chart.handle_events(Event::Key(KeyEvent::new(code, modifiers)), &mut state);
+    };
+    let none = KeyModifiers::empty();
+
+    // 24 months, Jan 2020 to Dec 2021
+    chart.data = vec![vec!["Month".into(), "query=nginx&facets=".into()]];
+    for month in 0..24 {
+        chart
+            .data
+            .push(vec![format!("Month {}", month), (month * 10).to_string()]);
+    }
+    assert_eq!(chart.window(24), (0, 23));
+
+    // Zoom in around the cursor, stops at 3 months
+    press(&mut chart, KeyCode::Home, none);
+    press(&mut chart, KeyCode::Char('+'), none);
+    assert_eq!(chart.window(24), (0, 11));
+    for _ in 0..5 {
+        press(&mut chart, KeyCode::Char('+'), none);
+    }
+    assert_eq!(chart.window(24), (0, 2));
+
+    // Pan keeps the cursor in the view, moving the cursor out of the view pans too
+    press(&mut chart, KeyCode::Right, KeyModifiers::SHIFT);
+    assert_eq!((chart.window(24), chart.cursor), ((1, 3), Some(1)));
+    press(&mut chart, KeyCode::End, none);
+    assert_eq!((chart.window(24), chart.cursor), ((21, 23), Some(23)));
+    press(&mut chart, KeyCode::Right, KeyModifiers::SHIFT);
+    assert_eq!(chart.window(24), (21, 23));
+
+    // Zoom out to the whole history
+    press(&mut chart, KeyCode::Char('-'), none);
+    assert_eq!(chart.window(24), (18, 23));
+    press(&mut chart, KeyCode::Char('0'), none);
+    assert_eq!(chart.view, None);
+
+    // Select the range with the cursor
+    press(&mut chart, KeyCode::Home, none);
+    press(&mut chart, KeyCode::Right, none);
+    press(&mut chart, KeyCode::Enter, none);
+    assert_eq!(chart.anchor, Some(1));
+    for _ in 0..5 {
+        press(&mut chart, KeyCode::Right, none);
+    }
+    press(&mut chart, KeyCode::Enter, none);
+    assert_eq!((chart.window(24), chart.anchor), ((1, 6), None));
+
+    // Fall back to the whole history if the view is out of fewer months
+    assert_eq!(chart.window(5), (0, 4));
+
+    Ok(())
+}
+
+#[test]
+fn scale_chart_y_axis() -> AppResult<()> {
+    let mut state = input_state();
+    let mut chart = LineChart::new();
+    chart.data = vec![
+        vec!["Month".into(), "query=nginx&facets=".into()],
+        vec!["Jan 2020".into(), "0".into()],
+    ];
+
+    // Cycle through the scales
+    for scale in [
+        YScale::Log,
+        YScale::Percent,
+        YScale::Indexed,
+        YScale::Linear,
+    ] {
+        chart.handle_events(
+            Event::Key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::empty())),
+            &mut state,
+        );
+        assert_eq!(chart.scale, scale);
+    }
+
+    let points = [(0.0, 0.0), (1.0, 50.0), (2.0, 200.0), (3.0, 100.0)];
+    assert_eq!(YScale::Linear.apply(&points), points);
+    assert_eq!(
+        YScale::Log.apply(&[(0.0, 0.0), (1.0, 9.0), (2.0, 999.0)]),
+        [(0.0, 0.0), (1.0, 1.0), (2.0, 3.0)]
+    );
+    assert_eq!(
+        YScale::Percent.apply(&points),
+        [(0.0, 0.0), (1.0, 25.0), (2.0, 100.0), (3.0, 50.0)]
+    );
+    // Indexed to the first month has results
+    assert_eq!(
+        YScale::Indexed.apply(&points),
+        [(0.0, 0.0), (1.0, 100.0), (2.0, 400.0), (3.0, 200.0)]
+    );
+    assert_eq!(YScale::Indexed.apply(&[(0.0, 0.0)]), [(0.0, 0.0)]);
+
+    // Ticks show counts on log scale
+    assert_eq!(YScale::Log.tick(0.0), "0");
+    assert_eq!(YScale::Log.tick(2.0), "99");
+    assert_eq!(YScale::Percent.tick(50.0), "50%");
+    assert_eq!(YScale::Indexed.tick(250.4), "250");
+
+    Ok(())
+}
+
+#[test]
+fn derive_chart_series() -> AppResult<()> {
+    let mut state = input_state();
+    let mut chart = LineChart::new();
+    let mut press = |chart: &mut LineChart, c: char| {
+        chart.handle_events(
+            Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty())),
+            &mut state,
+        );
+    };
+
+    // 14 months, the second line has no data of the first month
+    chart.data = vec![vec!["Month".into(), "nginx".into(), "apache".into()]];
+    for month in 0..14 {
+        let apache = match month {
+            0 => String::new(),
+            _ => (month * 2).to_string(),
+        };
+        chart.data.push(vec![
+            format!("Month {}", month),
+            (month * 10).to_string(),
+            apache,
+        ]);
+    }
+
+    let points: Vec<(f64, f64)> = (0..14).map(|x| (x as f64, (x * 10) as f64)).collect();
+    assert_eq!(Series::Counts.apply(&points), points);
+    assert_eq!(
+        Series::MonthOverMonth.apply(&points[..3]),
+        [(1.0, 10.0), (2.0, 10.0)]
+    );
+    // No percent change from zero results
+    assert_eq!(Series::YearOverYear.apply(&points), [(13.0, 1200.0)]);
+    assert_eq!(
+        Series::Rolling(3).apply(&points[..4]),
+        [(2.0, 10.0), (3.0, 20.0)]
+    );
+
+    // Cycle through the series, change months of the average
+    for series in [
+        Series::MonthOverMonth,
+        Series::YearOverYear,
+        Series::Rolling(3),
+    ] {
+        press(&mut chart, 'd');
+        assert_eq!(chart.series, series);
+    }
+    press(&mut chart, '>');
+    assert_eq!(chart.series, Series::Rolling(4));
+    for _ in 0..5 {
+        press(&mut chart, '<');
+    }
+    assert_eq!(chart.series, Series::Rolling(2));
+
+    // Exported columns of the average next to the counts
+    let data = chart.export_data();
+    assert_eq!(
+        data[0][3..],
+        ["nginx (2-month average)", "apache (2-month average)"]
+    );
+    assert_eq!(data[1][3..], ["", ""]);
+    assert_eq!(data[2][3..], ["5", ""]);
+    assert_eq!(data[3][3..], ["15", "3"]);
+
+    press(&mut chart, 'd');
+    assert_eq!(chart.export_data(), chart.data);
+
+    Ok(())
+}
+
+fn input_state() -> AppState {
+    let (sender, _) = mpsc::channel();
+    AppState {
+        focused: true,
+        submitted: false,
+        first_render: false,
+        facet_indexes: HashMap::new(),
+        facet_tabs: HashMap::new(),
+        app_log: String::new(),
+        sender,
+        keymap: Keymap::default(),
+    }
+}
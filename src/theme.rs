@@ -0,0 +1,199 @@
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use serde::{Deserialize, Serialize};
+
+use crate::query::TokenKind;
+use crate::ui::LINE_COLORS;
+
+/// Disables colors of any theme if set and not empty, https://no-color.org
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+// Okabe-Ito palette, distinguishable with common color vision deficiencies, black is grey on dark
+// terminals
+const COLORBLIND_COLORS: [Color; 8] = [
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(240, 228, 66),
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(153, 153, 153),
+];
+
+// Saturated colors for dark terminals
+const HIGH_CONTRAST_COLORS: [Color; 8] = [
+    Color::Rgb(255, 255, 0),
+    Color::Rgb(0, 255, 255),
+    Color::Rgb(255, 0, 255),
+    Color::Rgb(255, 255, 255),
+    Color::Rgb(0, 255, 0),
+    Color::Rgb(255, 128, 0),
+    Color::Rgb(100, 149, 255),
+    Color::Rgb(255, 105, 180),
+];
+
+// Markers of monochrome lines with their legend symbols, dimmed after all markers are used
+const MONOCHROME_MARKERS: [(Marker, &str); 4] = [
+    (Marker::Braille, "⣿"),
+    (Marker::Dot, "•"),
+    (Marker::Block, "█"),
+    (Marker::Bar, "▄"),
+];
+
+/// Colors of the TUI, chart lines and exported images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Default,
+    /// Lines in the Okabe-Ito palette, gains and losses in blue and orange
+    Colorblind,
+    HighContrast,
+    /// No colors, lines have distinct markers and dashes in images
+    Monochrome,
+}
+
+/// How a line is drawn on charts and marked in lists and legends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub color: Color,
+    pub modifier: Modifier,
+    pub marker: Marker,
+    /// Legend of the line, e.g. in the popup of chart values
+    pub symbol: &'static str,
+}
+
+impl LineStyle {
+    pub fn style(&self) -> Style {
+        Style::default().fg(self.color).add_modifier(self.modifier)
+    }
+}
+
+impl Theme {
+    /// Line colors of the theme, `colors` replace them unless monochrome.
+    pub fn line_colors(&self, colors: &[Color]) -> Vec<Color> {
+        match self {
+            Theme::Monochrome => vec![Color::Reset],
+            _ if !colors.is_empty() => colors.to_vec(),
+            Theme::Default => LINE_COLORS.to_vec(),
+            Theme::Colorblind => COLORBLIND_COLORS.to_vec(),
+            Theme::HighContrast => HIGH_CONTRAST_COLORS.to_vec(),
+        }
+    }
+
+    /// Styles of lines of queries and facet values, the nth line uses the nth style.
+    pub fn line_styles(&self, colors: &[Color]) -> Vec<LineStyle> {
+        match self {
+            Theme::Monochrome => [Modifier::empty(), Modifier::DIM]
+                .into_iter()
+                .flat_map(|modifier| {
+                    MONOCHROME_MARKERS
+                        .iter()
+                        .map(move |(marker, symbol)| LineStyle {
+                            color: Color::Reset,
+                            modifier,
+                            marker: *marker,
+                            symbol,
+                        })
+                })
+                .collect(),
+            _ => self
+                .line_colors(colors)
+                .into_iter()
+                .map(|color| LineStyle {
+                    color,
+                    modifier: Modifier::empty(),
+                    marker: Marker::Braille,
+                    symbol: "■",
+                })
+                .collect(),
+        }
+    }
+
+    /// Borders of the focused panel.
+    pub fn focused(&self) -> Style {
+        match self {
+            Theme::HighContrast => Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            Theme::Monochrome => Style::default().add_modifier(Modifier::BOLD),
+            _ => Style::default().fg(Color::Yellow),
+        }
+    }
+
+    /// Highlighted item of lists.
+    pub fn highlight(&self) -> Style {
+        match self {
+            Theme::HighContrast => Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            Theme::Monochrome => Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            _ => Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Less important texts, e.g. axes of unfocused charts.
+    pub fn muted(&self) -> Style {
+        match self {
+            Theme::HighContrast => Style::default().fg(Color::White),
+            Theme::Monochrome => Style::default(),
+            _ => Style::default().fg(Color::Gray),
+        }
+    }
+
+    /// API errors.
+    pub fn error(&self) -> Style {
+        match self {
+            Theme::Monochrome => Style::default().add_modifier(Modifier::BOLD),
+            Theme::Colorblind => Style::default().fg(Color::Rgb(213, 94, 0)),
+            _ => Style::default().fg(Color::Red),
+        }
+    }
+
+    /// Warnings, e.g. invalid queries or existing files.
+    pub fn warning(&self) -> Style {
+        match self {
+            Theme::Default | Theme::HighContrast => Style::default().fg(Color::LightRed),
+            _ => self.error(),
+        }
+    }
+
+    /// Symbols of gains or losses, e.g. of top movers.
+    pub fn change(&self, gain: bool) -> Style {
+        match (self, gain) {
+            (Theme::Monochrome, _) => Style::default(),
+            (Theme::Colorblind, true) => Style::default().fg(Color::Rgb(0, 114, 178)),
+            (Theme::Colorblind, false) => Style::default().fg(Color::Rgb(213, 94, 0)),
+            (Theme::HighContrast, true) => Style::default().fg(Color::LightGreen),
+            (Theme::HighContrast, false) => Style::default().fg(Color::LightRed),
+            (_, true) => Style::default().fg(Color::Green),
+            (_, false) => Style::default().fg(Color::Red),
+        }
+    }
+
+    /// Highlight of query tokens in the search box.
+    pub fn token(&self, kind: TokenKind) -> Style {
+        let style = Style::default();
+        match (self, kind) {
+            (_, TokenKind::Text | TokenKind::Whitespace) => style,
+            (_, TokenKind::UnknownFilter) => self.warning().add_modifier(Modifier::UNDERLINED),
+            (Theme::Monochrome, TokenKind::Filter) => style.add_modifier(Modifier::BOLD),
+            (Theme::Monochrome, TokenKind::Negation) => style.add_modifier(Modifier::ITALIC),
+            (Theme::Monochrome, _) => style,
+            (Theme::Colorblind, TokenKind::Filter) => style.fg(Color::Rgb(0, 114, 178)),
+            (Theme::Colorblind, TokenKind::Negation) => style.fg(Color::Rgb(204, 121, 167)),
+            (Theme::Colorblind, TokenKind::Value) => style.fg(Color::Rgb(86, 180, 233)),
+            (_, TokenKind::Filter) => style.fg(Color::Cyan),
+            (_, TokenKind::Negation) => style.fg(Color::Magenta),
+            (_, TokenKind::Value) => style.fg(Color::Green),
+            (_, TokenKind::QuotedValue) => style.fg(Color::Yellow),
+        }
+    }
+}
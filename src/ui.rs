@@ -1,10 +1,11 @@
+use human_repr::HumanCount;
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::vec;
+use unicode_width::UnicodeWidthStr;
 
-use crate::components::KeySymbols;
+use crate::keymap::{Action, Keymap};
 use crate::widgets::list::{List as MultiList, ListItem as MultiListItem};
-use human_repr::HumanCount;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 use ratatui::{
@@ -13,14 +14,22 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
-use url::form_urlencoded;
 
-use crate::app::App;
 use crate::app::AppState;
+use crate::app::{decode_query, decode_range, y_range_ticks, y_ticks, App};
+use crate::components::export_dialog::ExportDialog;
+use crate::components::line_chart::{Series, YScale};
+use crate::components::user_input::UserInput;
 use crate::components::Component;
+use crate::export::ExportFormat;
+use crate::image::{ChartImage, ImageLine};
+use crate::query;
+use crate::range::DateRange;
+use crate::stats::{format_change, format_percent, Stats};
+use crate::theme::{LineStyle, Theme};
 
 // Pre parsed Trends Rgb colors from hex with https://github.com/emgyrz/colorsys.rs
-const LINE_COLORS: [Color; 30] = [
+pub const LINE_COLORS: [Color; 30] = [
     Color::Rgb(213, 5, 39),
     Color::Rgb(21, 137, 64),
     Color::Rgb(248, 152, 253),
@@ -52,8 +61,11 @@ const LINE_COLORS: [Color; 30] = [
     Color::Rgb(249, 43, 117),
     Color::Rgb(7, 201, 157),
 ];
-const MAX_SAVED_QUERIES: usize = 5;
-const SELECTED_FACET_LINES: usize = 5;
+// Fit a range of two months and the cursor, e.g. 2022-01..2023-06
+const RANGE_INPUT_WIDTH: u16 = 18;
+// Fit month labels and counts of the stats, e.g. Latest  Jul 2023 37.05M
+const STATS_WIDTH: u16 = 34;
+const EXPORT_DIALOG_WIDTH: u16 = 64;
 
 /// Renders the user interface widgets.
 // - https://docs.rs/ratatui/latest/ratatui/widgets/index.html
@@ -65,8 +77,9 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
     // - Search query returns no results or errored out
 
     // Truncate data before rendering
-    while app.charts.len() > MAX_SAVED_QUERIES {
+    while app.charts.len() > app.config.max_saved_queries {
         app.charts.remove_entry(&app.queries[0]);
+        app.searched.remove(&app.queries[0]);
         app.queries.remove(0);
     }
 
@@ -93,39 +106,57 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
         )
         .split(frame.size());
 
+    // Areas are updated on rendering, hidden or not rendered panels can't be clicked
+    for widget in app.get_widgets() {
+        widget.set_area(Rect::default());
+    }
+    // Values of the chart under the mouse, e.g. (nginx, Red)
+    let mut chart_labels: Vec<(String, LineStyle)> = vec![];
+
+    // Keys are labelled from the keymap, so remapped keys are shown
+    let keymap = &state.keymap;
     let mut help_keys = vec![];
     let mut default_keys = vec![
-        format!("Switch panels [{}]", KeySymbols::TAB),
-        format!("Exit [{}C]", KeySymbols::CONTROL),
+        keymap.help("Switch panels", &[Action::SwitchPanel]),
+        keymap.help("Exit", &[Action::Quit]),
     ];
 
-    if !app.search_input.focused() && !app.facets_input.focused() && !app.line_chart.data.is_empty()
+    if !app.search_input.focused()
+        && !app.facets_input.focused()
+        && !app.range_input.focused()
+        && !app.line_chart.data.is_empty()
     {
         default_keys.insert(
             default_keys.len() - 1,
-            format!("Export [{}E]", KeySymbols::CONTROL),
+            keymap.help("Export", &[Action::Export]),
         );
     }
 
     // Get focused widget keys
-    for (_, widget) in app.get_widgets().into_iter().enumerate() {
+    for widget in app.get_widgets().into_iter() {
         if widget.focused() && !widget.hidden() {
-            help_keys = widget.help_keys().to_owned();
-            help_keys.push(format!("Unfocused [{}]", KeySymbols::ESC));
+            help_keys = widget.help_keys(keymap).to_owned();
+            help_keys.extend(keymap.help("Unfocused", &[Action::Unfocus]));
             break;
         }
     }
 
+    // Export dialog takes all keys until closed
+    if !app.export_dialog.hidden() {
+        help_keys = app.export_dialog.help_keys(keymap);
+        default_keys = vec![keymap.help("Exit", &[Action::Quit])];
+    }
+
     // Append default keys
-    help_keys.append(&mut default_keys.to_owned());
+    help_keys.extend(default_keys.into_iter().flatten());
     let footer_padding = Padding {
         left: 0,
         right: 0,
         top: 1,
         bottom: 0,
     };
-    let help_commands = Paragraph::new(help_keys.join("  "))
-        .block(Block::default().padding(footer_padding.clone()));
+    let help_commands =
+        Paragraph::new(help_keys.join("  ")).block(Block::default().padding(footer_padding));
 
     // Show application log if any, e.g. Export chart to ./data.csv
     if !state.app_log.is_empty() {
@@ -144,18 +175,19 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
     }
 
     // Some reuse widgets
+    let theme = app.config.theme;
     let error_block = Block::default()
         .title("Error")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Red))
-        .style(Style::default().fg(Color::Red));
+        .border_style(theme.error())
+        .style(theme.error());
     let error_widget = Paragraph::new(format!(
         "{}\n\
         API documentation: https://developer.shodan.io/api/trends
     ",
         app.api_error.to_owned()
     ))
-    .style(Style::default().fg(Color::Red))
+    .style(theme.error())
     .alignment(Alignment::Center);
     let info_block = Block::default().title("Info").borders(Borders::ALL);
     let no_results_widget: Paragraph<'_> =
@@ -173,12 +205,15 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
         )
         .split(layouts[1]);
 
-    let colors_len = LINE_COLORS.len();
-    let focused_style = Style::default().fg(Color::Yellow);
-    let search_box_style = match app.search_input.focused() || app.facets_input.focused() {
-        true => focused_style,
-        false => Style::default(),
-    };
+    let line_styles = app.config.line_styles();
+    let styles_len = line_styles.len();
+    let focused_style = theme.focused();
+    let search_box_style =
+        match app.search_input.focused() || app.facets_input.focused() || app.range_input.focused()
+        {
+            true => focused_style,
+            false => Style::default(),
+        };
 
     // Group 4 blocks into 1 search box block and have custom borders around it
     let search_box_layouts = Layout::default()
@@ -198,12 +233,15 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
         .split(search_box_layouts[0]);
 
     let facet_prefix_text = "Facets (optional):";
+    let range_prefix_text = "Months:";
     let facet_layouts = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(
             [
                 Constraint::Length(facet_prefix_text.len() as u16 + 3),
                 Constraint::Min(0),
+                Constraint::Length(range_prefix_text.len() as u16 + 2),
+                Constraint::Length(RANGE_INPUT_WIDTH),
             ]
             .as_ref(),
         )
@@ -224,10 +262,24 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
     );
     frame.render_widget(search_prefix, search_layouts[0]);
 
-    let search_query = Paragraph::new(app.search_input.get_input()).block(
+    // Scroll long inputs horizontally, minus right border if any
+    app.search_input
+        .scroll(search_layouts[1].width.saturating_sub(1) as usize);
+    app.facets_input.scroll(facet_layouts[1].width as usize);
+    app.range_input
+        .scroll(facet_layouts[3].width.saturating_sub(1) as usize);
+
+    let search_query = Paragraph::new(query_line(
+        app.search_input.get_input(),
+        app.search_input.visible_input(),
+        app.search_input.completion_names(),
+        theme,
+    ))
+    .block(
         Block::default()
             .borders(Borders::TOP | Borders::RIGHT)
-            .border_style(search_box_style),
+            .border_style(search_box_style)
+            .title(history_search_title(app)),
     );
     frame.render_widget(search_query, search_layouts[1]);
 
@@ -246,27 +298,97 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
     );
     frame.render_widget(facet_prefix, facet_layouts[0]);
 
-    let search_facets = Paragraph::new(app.facets_input.get_input()).block(
+    let search_facets = Paragraph::new(app.facets_input.visible_input()).block(
         Block::default()
-            .borders(Borders::RIGHT | Borders::BOTTOM)
+            .borders(Borders::BOTTOM)
             .border_style(search_box_style),
     );
     frame.render_widget(search_facets, facet_layouts[1]);
 
+    let range_prefix = Paragraph::new(vec![Line::from(Span::styled(
+        range_prefix_text,
+        match app.range_input.focused() {
+            true => Style::default().bold(),
+            _ => Style::default(),
+        },
+    ))])
+    .block(
+        Block::default()
+            .borders(Borders::BOTTOM)
+            .border_style(search_box_style)
+            .padding(Padding::new(1, 0, 0, 0)),
+    );
+    frame.render_widget(range_prefix, facet_layouts[2]);
+
+    let search_range = Paragraph::new(app.range_input.visible_input()).block(
+        Block::default()
+            .borders(Borders::RIGHT | Borders::BOTTOM)
+            .border_style(search_box_style),
+    );
+    frame.render_widget(search_range, facet_layouts[3]);
+
+    // Input lines, minus borders
+    app.search_input.set_area(Rect::new(
+        search_layouts[1].x,
+        search_layouts[1].y + 1,
+        search_layouts[1].width.saturating_sub(1),
+        1,
+    ));
+    app.facets_input.set_area(Rect::new(
+        facet_layouts[1].x,
+        facet_layouts[1].y,
+        facet_layouts[1].width,
+        1,
+    ));
+    app.range_input.set_area(Rect::new(
+        facet_layouts[3].x,
+        facet_layouts[3].y,
+        facet_layouts[3].width.saturating_sub(1),
+        1,
+    ));
+
+    // Warn invalid query/ facets on the bottom border of search box
+    let warning = query::check_query(
+        app.search_input.get_input(),
+        app.search_input.completion_names(),
+    )
+    .or(query::check_facets(app.facets_input.get_input()))
+    .or(DateRange::parse(app.range_input.get_input()).err());
+    if let Some(warning) = warning {
+        let area = layouts[0];
+        let warning = Paragraph::new(format!(" {} ", warning))
+            .style(theme.warning())
+            .alignment(Alignment::Right);
+        frame.render_widget(
+            warning,
+            Rect::new(
+                area.x + 1,
+                area.bottom().saturating_sub(1),
+                area.width.saturating_sub(2),
+                1,
+            ),
+        );
+    }
+
     // Show cursor if focused in UserInput
     if app.search_input.focused() {
         frame.set_cursor(
             // Draw the cursor at the current position in the input field.
             // This position is can be controlled via the left and right arrow key
-            search_layouts[1].x + app.search_input.cursor_position as u16,
+            search_layouts[1].x + app.search_input.cursor_column(),
             // Move one line down, from the border to the input line
             search_layouts[1].y + 1,
         );
     } else if app.facets_input.focused() {
         frame.set_cursor(
-            facet_layouts[1].x + app.facets_input.cursor_position as u16,
+            facet_layouts[1].x + app.facets_input.cursor_column(),
             facet_layouts[1].y,
         );
+    } else if app.range_input.focused() {
+        frame.set_cursor(
+            facet_layouts[3].x + app.range_input.cursor_column(),
+            facet_layouts[3].y,
+        );
     }
 
     // API request in the background
@@ -322,6 +444,20 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
         let sidebar_layouts = Layout::default()
             .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
             .split(main_layouts[0]);
+        // Stats panel on the right of the chart
+        let chart_layouts = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Min(0),
+                    Constraint::Length(match app.line_chart.stats {
+                        true => STATS_WIDTH,
+                        false => 0,
+                    }),
+                ]
+                .as_ref(),
+            )
+            .split(main_layouts[1]);
 
         // Error on the right side if any
         if !app.api_error.is_empty() || app.no_results {
@@ -365,15 +501,15 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
         // Build saved queries block
         let mut query_lines: Vec<String> = vec![];
         let mut query_items: Vec<MultiListItem> = vec![];
-        // Color mapping used to draw chart later
-        let mut query_colors: HashMap<String, Color> = HashMap::new();
+        // Line styles used to draw chart later
+        let mut query_styles: HashMap<String, LineStyle> = HashMap::new();
 
         for (index, query) in app.queries.iter().rev().enumerate() {
-            let label_color = LINE_COLORS[index];
-            query_colors.insert(query.to_owned(), label_color);
+            let line_style = line_styles[index % styles_len];
+            query_styles.insert(query.to_owned(), line_style);
 
-            let lines = vec![Line::from(vec![query.to_owned().into()])];
-            query_items.push(MultiListItem::new(lines).style(Style::default().fg(label_color)));
+            let lines = vec![legend_line(query, &line_style, theme)];
+            query_items.push(MultiListItem::new(lines).style(line_style.style()));
             query_lines.push(query.to_owned());
         }
         app.saved_queries.set_items(query_lines.clone());
@@ -388,12 +524,7 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
                         _ => Style::default(),
                     }),
             )
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::LightYellow)
-                    .add_modifier(Modifier::BOLD),
-            )
+            .highlight_style(theme.highlight())
             .highlight_symbol(" [x] ")
             .unselect_symbol(" [ ] ");
 
@@ -430,14 +561,11 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
 
                 // Load correct query/ facets in search box if select differently with previous
                 if selected_query != &app.prev_query {
-                    let decoded_query = form_urlencoded::parse(selected_query.as_bytes());
-                    for (key, val) in decoded_query.into_iter() {
-                        if key == "query" {
-                            app.search_input.set_input(&val);
-                        } else if key == "facets" {
-                            app.facets_input.set_input(&val);
-                        }
-                    }
+                    let (query, facets) = decode_query(selected_query);
+                    app.search_input.set_input(&query);
+                    app.facets_input.set_input(&facets);
+                    app.range_input
+                        .set_input(&decode_range(selected_query).to_string());
                 }
             }
         }
@@ -448,10 +576,14 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
             sidebar_layouts[0],
             &mut app.saved_queries.state,
         );
+        app.saved_queries.set_area(sidebar_layouts[0]);
         frame.render_widget(facet_values, sidebar_layouts[1]);
 
-        // Default draw total chart if unfocused facet values block
-        if !app.facet_values.focused() {
+        // Default draw total chart if unfocused facet values block, keep the facets chart on moving
+        // to the chart to inspect facet values with the cursor
+        app.facets_chart =
+            app.facet_values.focused() || (app.facets_chart && app.line_chart.focused());
+        if !app.facets_chart {
             // Construct export data in CSV format which easy load to Excel for generate charts
             // Month    | query=nginx&facets=asn%3A10 | query=apache&facets=org
             // Jun 2017 | 19799459,27382961           |
@@ -460,55 +592,121 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
             // Jul 2023 | 37054878,20837852           |
             let mut chart_data: Vec<Vec<String>> = vec![vec!["Month".to_string()]];
             let mut datasets = vec![];
-
-            // Just get one X Axis as it's same for all charts
-            let mut x_bounds = vec![];
-            let mut x_ticks = vec![];
-            if let Some(entry) = app.charts.last_entry() {
-                x_bounds = entry.get().x_bounds.clone();
-                x_ticks = entry.get().x_ticks.clone();
-                for month in &entry.get().x_labels {
-                    chart_data.push(vec![month.to_owned()]);
-                }
+            let selected_queries: Vec<&String> = app
+                .saved_queries
+                .state
+                .selected_indexes()
+                .iter()
+                .map(|index| &app.queries[*index])
+                .filter(|query| app.charts.contains_key(*query))
+                .collect();
+
+            // One X Axis of all months of selected charts as queries could have different months range
+            let mut x_axis: BTreeMap<&String, &String> = BTreeMap::new();
+            for query in &selected_queries {
+                let chart = &app.charts[*query];
+                x_axis.extend(chart.months.iter().zip(chart.x_labels.iter()));
+            }
+            let months: Vec<&String> = x_axis.keys().copied().collect();
+            let x_labels: Vec<String> = x_axis.values().map(|label| label.to_string()).collect();
+            for label in &x_labels {
+                chart_data.push(vec![label.to_owned()]);
             }
 
+            // Only visible months on zoomed in
+            let window = app.line_chart.window(months.len());
+            let zoomed = window != (0, months.len().saturating_sub(1));
+            let x_bounds = [window.0 as f64, window.1 as f64];
+
             // Have to rebuild Y Axis data from selected charts
+            let mut min_y_axis = f64::MAX;
             let mut max_y_axis = 0.0;
-
-            for index in app.saved_queries.state.selected_indexes() {
-                let query = &app.queries[*index];
-                if let Some(chart) = app.charts.get(query) {
-                    datasets.push(
-                        Dataset::default()
-                            // .name(query.to_owned())
-                            .marker(symbols::Marker::Braille)
-                            .graph_type(GraphType::Line)
-                            .style(Style::default().fg(query_colors[query]))
-                            .data(&chart.datasets[0].data),
-                    );
-
-                    let chart_y_axis = chart.y_bounds[chart.y_bounds.len() - 1];
-                    if chart_y_axis > max_y_axis {
-                        max_y_axis = chart_y_axis;
-                    }
-
-                    // Build chart data
-                    chart_data[0].push(query.to_string());
-                    for (i, point) in chart.datasets[0].data.iter().enumerate() {
-                        chart_data[i + 1].push(point.1.to_string());
-                    }
+            let mut lines: Vec<(&String, Vec<(f64, f64)>)> = vec![];
+            let series = app.line_chart.series;
+            let (scale, axis_scale) = series_scales(series, app.line_chart.scale);
+
+            for query in selected_queries {
+                let chart = &app.charts[query];
+                // Shift points to the month on X Axis
+                let offset = months
+                    .iter()
+                    .position(|month| Some(*month) == chart.months.first())
+                    .unwrap_or(0);
+                let points: Vec<(f64, f64)> = series
+                    .apply(&chart.datasets[0].data)
+                    .iter()
+                    .map(|(x, y)| (x + offset as f64, *y))
+                    .filter(|(x, _)| *x >= x_bounds[0] && *x <= x_bounds[1])
+                    .collect();
+                let points = scale.apply(&points);
+
+                let chart_y_axis = max_y(&points);
+                if chart_y_axis > max_y_axis {
+                    max_y_axis = chart_y_axis;
                 }
+                min_y_axis = min_y_axis.min(min_y(&points));
+
+                // Build chart data, empty if the query has no data of the month
+                chart_data[0].push(query.to_string());
+                for (i, row) in chart_data.iter_mut().skip(1).enumerate() {
+                    let value = i
+                        .checked_sub(offset)
+                        .and_then(|i| chart.datasets[0].data.get(i));
+                    row.push(value.map_or(String::new(), |point| point.1.to_string()));
+                }
+                lines.push((query, points));
+            }
+
+            for (query, points) in &lines {
+                datasets.push(
+                    Dataset::default()
+                        // .name(query.to_owned())
+                        .marker(query_styles[*query].marker)
+                        .graph_type(GraphType::Line)
+                        .style(query_styles[*query].style())
+                        .data(points),
+                );
             }
 
-            let y_bounds = vec![0.0, max_y_axis];
-            // Convert float to human-readable format
-            let y_ticks = vec![
-                String::from("0"),
-                ((max_y_axis / 2.0) as i64).human_count_bare().to_string(),
-                (max_y_axis as i64).human_count_bare().to_string(),
-            ];
+            let (y_bounds, y_ticks) = y_axis(min_y_axis, max_y_axis, zoomed, axis_scale);
 
             if !datasets.is_empty() {
+                let x_ticks = crate::app::x_ticks(&x_labels[window.0..=window.1]);
+                app.line_chart
+                    .set_area(graph_area(chart_layouts[0], &x_ticks, &y_ticks));
+                app.line_chart.set_x_bounds(x_bounds);
+                chart_labels = chart_data[0][1..]
+                    .iter()
+                    .map(|query| (decode_query(query).0, query_styles[query]))
+                    .collect();
+                app.line_chart.image = ChartImage {
+                    // Title of the query if only one
+                    title: match lines.as_slice() {
+                        [(query, _)] => format!("Total results: {}", decode_query(query).0),
+                        _ => "Total results".to_string(),
+                    },
+                    y_title: y_title(series, scale),
+                    x_bounds,
+                    x_ticks: x_ticks.clone(),
+                    // Counts on hovering points, scaled values aren't counts
+                    x_labels: match (series, scale) {
+                        (Series::Counts, YScale::Linear) => x_labels.clone(),
+                        _ => vec![],
+                    },
+                    y_bounds: [y_bounds[0], y_bounds[y_bounds.len() - 1]],
+                    y_ticks: y_ticks.clone(),
+                    stacked: false,
+                    dashed: theme == Theme::Monochrome,
+                    lines: lines
+                        .iter()
+                        .map(|(query, points)| ImageLine {
+                            label: decode_query(query).0,
+                            color: query_styles[*query].color,
+                            points: points.to_owned(),
+                        })
+                        .collect(),
+                };
+
                 let query_chart = Chart::new(datasets)
                     .block(
                         Block::default()
@@ -519,24 +717,30 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
                         Axis::default()
                             .style(match app.line_chart.focused() {
                                 true => focused_style,
-                                false => Style::default().fg(Color::Gray),
+                                false => theme.muted(),
                             })
-                            .bounds([x_bounds[0], x_bounds[x_bounds.len() - 1]])
+                            .bounds(x_bounds)
                             .labels(x_ticks.iter().cloned().map(Span::from).collect()),
                     )
                     .y_axis(
                         Axis::default()
-                            .title(Span::styled("Total results", Style::default()))
+                            .title(Span::styled(
+                                match y_title(series, scale).as_str() {
+                                    "" => "Total results".to_string(),
+                                    title => format!("Total results ({})", title),
+                                },
+                                Style::default(),
+                            ))
                             .style(match app.line_chart.focused() {
                                 true => focused_style,
-                                false => Style::default().fg(Color::Gray),
+                                false => theme.muted(),
                             })
                             .bounds([y_bounds[0], y_bounds[y_bounds.len() - 1]])
                             .labels(y_ticks.iter().cloned().map(Span::from).collect())
                             .labels_alignment(Alignment::Center),
                     );
 
-                frame.render_widget(query_chart, main_layouts[1]);
+                frame.render_widget(query_chart, chart_layouts[0]);
             }
 
             if app.line_chart.data != chart_data {
@@ -547,163 +751,751 @@ pub fn render(app: &mut App, state: &mut AppState, frame: &mut Frame<'_>) {
 
         // Build facets blocks corresponding to selected query
         if let Some(chart) = app.charts.get(selected_query) {
-            let mut facet_colors: HashMap<String, Color> = HashMap::new();
+            let mut facet_styles: HashMap<String, LineStyle> = HashMap::new();
             let mut facet_lines: Vec<String> = vec![];
 
-            // Load facet values if any
-            match &chart.facets {
-                Some(chart) => {
-                    let mut facet_items: Vec<MultiListItem> = vec![];
-
-                    for (mut index, point) in chart.datasets.iter().enumerate() {
-                        // Just a bit catch, but 30 defined colors are too many, and we shouldn't overflow widget
-                        while index >= colors_len {
-                            index -= colors_len;
-                        }
-                        let label_color = LINE_COLORS[index];
-                        facet_colors.insert(point.label.to_owned(), label_color);
-
-                        let lines = vec![Line::from(vec![point.label.to_owned().into()])];
-                        facet_items.push(
-                            MultiListItem::new(lines).style(Style::default().fg(label_color)),
-                        );
-                        facet_lines.push(point.label.to_owned());
+            // Load facet values if any, each requested facet has its own tab
+            if !chart.facets.is_empty() {
+                // Attach state key for saving indexes later on Enter
+                app.facet_values
+                    .set_state_key(Some(selected_query.to_owned()));
+                app.facet_values.set_tabs(
+                    chart
+                        .facets
+                        .iter()
+                        .map(|chart| chart.name.to_owned())
+                        .collect(),
+                    state.facet_tabs.get(selected_query).cloned().unwrap_or(0),
+                );
+                let chart = &chart.facets[app.facet_values.tab_index];
+                let indexes_key = app.facet_values.indexes_key().unwrap_or_default();
+                let mut facet_items: Vec<MultiListItem> = vec![];
+
+                for (mut index, point) in chart.datasets.iter().enumerate() {
+                    // Just a bit catch, but 30 defined colors are too many, and we shouldn't overflow widget
+                    while index >= styles_len {
+                        index -= styles_len;
                     }
+                    let line_style = line_styles[index];
+                    facet_styles.insert(point.label.to_owned(), line_style);
 
-                    // Highlight facet lines
-                    if !facet_lines.is_empty() {
-                        match state.facet_indexes.get(selected_query) {
-                            Some(facet_index) => {
-                                // Load previous selected indexes
-                                app.facet_values
-                                    .state
-                                    .with_selected_indexes(facet_index.selected_indexes.to_owned());
-
-                                if selected_query != &app.prev_query {
-                                    app.facet_values.state.select(facet_index.selected);
-                                }
-                            }
-                            None => {
-                                // On first initialize
-                                app.facet_values.state.with_selected_indexes(Vec::from_iter(
-                                    0..(cmp::min(SELECTED_FACET_LINES, facet_lines.len())),
-                                ));
-                                app.facet_values
-                                    .state
-                                    .select(Some(app.facet_values.state.selected_indexes().len()));
+                    let lines = vec![legend_line(&point.label, &line_style, theme)];
+                    facet_items.push(MultiListItem::new(lines).style(line_style.style()));
+                    facet_lines.push(point.label.to_owned());
+                }
+
+                // Highlight facet lines
+                if !facet_lines.is_empty() {
+                    match state.facet_indexes.get(&indexes_key) {
+                        Some(facet_index) => {
+                            // Load previous selected indexes
+                            app.facet_values
+                                .state
+                                .with_selected_indexes(facet_index.selected_indexes.to_owned());
+
+                            // Also on switched facet tab
+                            if selected_query != &app.prev_query
+                                || app.facet_values.state.selected().is_none()
+                            {
+                                app.facet_values.state.select(facet_index.selected);
                             }
                         }
-                        // Attach state key for saving indexes later on Enter
-                        app.facet_values
-                            .set_state_key(Some(selected_query.to_owned()));
+                        None => {
+                            // On first initialize
+                            app.facet_values.state.with_selected_indexes(Vec::from_iter(
+                                0..(cmp::min(app.config.selected_facet_lines, facet_lines.len())),
+                            ));
+                            app.facet_values
+                                .state
+                                .select(Some(app.facet_values.state.selected_indexes().len()));
+                        }
                     }
-                    app.facet_values.set_items(facet_lines.clone());
-
-                    let facet_values: MultiList<'_> = MultiList::new(facet_items)
-                        .block(
-                            Block::default()
-                                .borders(Borders::ALL)
-                                .title("Facet values")
-                                .border_style(match app.facet_values.focused() {
-                                    true => focused_style,
-                                    _ => Style::default(),
-                                }),
-                        )
-                        .highlight_style(
-                            Style::default()
-                                .fg(Color::Black)
-                                .bg(Color::LightYellow)
-                                .add_modifier(Modifier::BOLD),
-                        );
-                    frame.render_stateful_widget(
-                        facet_values,
-                        sidebar_layouts[1],
-                        &mut app.facet_values.state,
-                    );
-
-                    // Load facets chart
-                    if app.facet_values.focused() {
-                        let selected_facets = app.facet_values.state.selected_indexes();
-                        let mut chart_data: Vec<Vec<String>> = vec![vec!["Month".to_string()]];
-                        let mut datasets = vec![];
-
-                        for month in &chart.x_labels {
-                            chart_data.push(vec![month.to_owned()]);
+                }
+                app.facet_values.set_items(facet_lines.clone());
+
+                // Show facet names as tabs in block title if requested many facets, e.g. os | country
+                let mut facet_title = vec![];
+                if app.facet_values.tabs.len() > 1 {
+                    for (index, tab) in app.facet_values.tabs.iter().enumerate() {
+                        if index > 0 {
+                            facet_title.push(Span::from("|"));
                         }
+                        facet_title.push(match index == app.facet_values.tab_index {
+                            true => Span::styled(
+                                format!(" {} ", tab),
+                                Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                            ),
+                            false => Span::from(format!(" {} ", tab)),
+                        });
+                    }
+                } else {
+                    facet_title.push(Span::from("Facet values"));
+                }
 
-                        for (_, point) in chart
+                let facet_values: MultiList<'_> = MultiList::new(facet_items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(Line::from(facet_title))
+                            .border_style(match app.facet_values.focused() {
+                                true => focused_style,
+                                _ => Style::default(),
+                            }),
+                    )
+                    .highlight_style(theme.highlight());
+                frame.render_stateful_widget(
+                    facet_values,
+                    sidebar_layouts[1],
+                    &mut app.facet_values.state,
+                );
+                app.facet_values.set_area(sidebar_layouts[1]);
+
+                // Load facets chart
+                if app.facets_chart {
+                    let selected_facets = app.facet_values.state.selected_indexes();
+                    let mut chart_data: Vec<Vec<String>> = vec![vec!["Month".to_string()]];
+                    let mut datasets = vec![];
+
+                    // Scale Y axis to all facet values in visible months
+                    let window = app.line_chart.window(chart.x_labels.len());
+                    let zoomed = window != (0, chart.x_labels.len() - 1);
+                    let series = app.line_chart.series;
+                    let (scale, axis_scale) = series_scales(series, app.line_chart.scale);
+                    let stacked = app.facet_values.stacked;
+                    let lines: Vec<Vec<(f64, f64)>> = match stacked {
+                        true => chart
+                            .shares()
+                            .iter()
+                            .map(|point| visible_points(&point.data, window))
+                            .collect(),
+                        false => chart
+                            .datasets
+                            .iter()
+                            .map(|point| {
+                                scale.apply(&visible_points(&series.apply(&point.data), window))
+                            })
+                            .collect(),
+                    };
+                    let (y_bounds, y_ticks) = match stacked {
+                        true => y_axis(0.0, 100.0, false, YScale::Percent),
+                        false => {
+                            let max_y_axis =
+                                lines.iter().map(|points| max_y(points)).fold(0.0, f64::max);
+                            let min_y_axis = lines
+                                .iter()
+                                .map(|points| min_y(points))
+                                .fold(f64::MAX, f64::min);
+                            y_axis(min_y_axis, max_y_axis, zoomed, axis_scale)
+                        }
+                    };
+                    let x_ticks = crate::app::x_ticks(&chart.x_labels[window.0..=window.1]);
+                    let area = graph_area(chart_layouts[0], &x_ticks, &y_ticks);
+
+                    // Stack shares of selected facet values in the order of the facet values
+                    // block, same as datasets, the first one at the bottom
+                    let mut areas: Vec<Vec<(f64, f64)>> = vec![];
+                    let mut image_lines: Vec<ImageLine> = vec![];
+                    if stacked {
+                        let mut bottom = vec![0.0; window.1 - window.0 + 1];
+                        for (index, point) in chart
                             .datasets
                             .iter()
                             .enumerate()
                             .filter(|(index, _)| selected_facets.contains(index))
                         {
-                            datasets.push(
-                                Dataset::default()
-                                    // Disable chart legend as we already show color in facet values block,
-                                    // current legend won't display if facet line too long.
-                                    // .name(point.label.to_owned())
-                                    .marker(symbols::Marker::Braille)
-                                    .graph_type(GraphType::Line)
-                                    .style(
-                                        Style::default().fg(facet_colors[&point.label.to_owned()]),
-                                    )
-                                    .data(&point.data),
-                            );
-
-                            // Build saved data
-                            chart_data[0].push(point.label.to_owned());
-                            for (i, point) in point.data.iter().enumerate() {
-                                chart_data[i + 1].push(point.1.to_string());
-                            }
+                            let top: Vec<f64> = bottom
+                                .iter()
+                                .zip(&lines[index])
+                                .map(|(bottom, point)| bottom + point.1)
+                                .collect();
+                            areas.push(fill_area(window.0, &bottom, &top, area));
+                            image_lines.push(ImageLine {
+                                label: point.label.to_owned(),
+                                color: facet_styles[&point.label].color,
+                                points: top
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(month, y)| ((window.0 + month) as f64, *y))
+                                    .collect(),
+                            });
+                            bottom = top;
+                        }
+                    }
+
+                    for month in &chart.x_labels {
+                        chart_data.push(vec![month.to_owned()]);
+                    }
+
+                    for (nth, (index, point)) in chart
+                        .datasets
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| selected_facets.contains(index))
+                        .enumerate()
+                    {
+                        datasets.push(
+                            Dataset::default()
+                                // Disable chart legend as we already show color in facet values block,
+                                // current legend won't display if facet line too long.
+                                // .name(point.label.to_owned())
+                                .marker(facet_styles[&point.label].marker)
+                                .graph_type(match stacked {
+                                    true => GraphType::Scatter,
+                                    false => GraphType::Line,
+                                })
+                                .style(facet_styles[&point.label].style())
+                                .data(match stacked {
+                                    true => &areas[nth],
+                                    false => &lines[index],
+                                }),
+                        );
+
+                        if !stacked {
+                            image_lines.push(ImageLine {
+                                label: point.label.to_owned(),
+                                color: facet_styles[&point.label].color,
+                                points: lines[index].to_owned(),
+                            });
                         }
 
-                        // Don't want to override if data is the same
-                        if app.line_chart.data != chart_data {
-                            app.line_chart.data = chart_data;
+                        // Build saved data
+                        chart_data[0].push(point.label.to_owned());
+                        for (i, point) in point.data.iter().enumerate() {
+                            chart_data[i + 1].push(point.1.to_string());
                         }
+                    }
 
-                        let facet_chart = Chart::new(datasets)
-                            .block(
-                                Block::default()
-                                    .borders(Borders::NONE)
-                                    .padding(Padding::new(1, 0, 1, 0)),
-                            )
-                            .x_axis(
-                                Axis::default()
-                                    .style(match app.line_chart.focused() {
-                                        true => focused_style,
-                                        false => Style::default().fg(Color::Gray),
-                                    })
-                                    .bounds([
-                                        chart.x_bounds[0],
-                                        chart.x_bounds[chart.x_bounds.len() - 1],
-                                    ])
-                                    .labels(
-                                        chart.x_ticks.iter().cloned().map(Span::from).collect(),
-                                    ),
-                            )
-                            .y_axis(
-                                Axis::default()
-                                    .style(match app.line_chart.focused() {
-                                        true => focused_style,
-                                        false => Style::default().fg(Color::Gray),
-                                    })
-                                    .bounds([
-                                        chart.y_bounds[0],
-                                        chart.y_bounds[chart.y_bounds.len() - 1],
-                                    ])
-                                    .labels(chart.y_ticks.iter().cloned().map(Span::from).collect())
-                                    .labels_alignment(Alignment::Center),
-                            );
-
-                        frame.render_widget(facet_chart, main_layouts[1]);
+                    // Don't want to override if data is the same
+                    if app.line_chart.data != chart_data {
+                        app.line_chart.data = chart_data;
                     }
+
+                    app.line_chart.image = ChartImage {
+                        title: format!("{} by {}", decode_query(selected_query).0, chart.name),
+                        y_title: match stacked {
+                            true => "% share".to_string(),
+                            false => y_title(series, scale),
+                        },
+                        x_bounds: [window.0 as f64, window.1 as f64],
+                        x_ticks: x_ticks.clone(),
+                        x_labels: match (series, scale) {
+                            (Series::Counts, YScale::Linear) => chart.x_labels.clone(),
+                            _ => vec![],
+                        },
+                        y_bounds,
+                        y_ticks: y_ticks.clone(),
+                        stacked,
+                        dashed: theme == Theme::Monochrome,
+                        lines: image_lines,
+                    };
+
+                    app.line_chart.set_area(area);
+                    app.line_chart
+                        .set_x_bounds([window.0 as f64, window.1 as f64]);
+                    chart_labels = app.line_chart.data[0][1..]
+                        .iter()
+                        .map(|label| (label.to_owned(), facet_styles[label]))
+                        .collect();
+
+                    let facet_chart = Chart::new(datasets)
+                        .block(
+                            Block::default()
+                                .borders(Borders::NONE)
+                                .padding(Padding::new(1, 0, 1, 0)),
+                        )
+                        .x_axis(
+                            Axis::default()
+                                .style(match app.line_chart.focused() {
+                                    true => focused_style,
+                                    false => theme.muted(),
+                                })
+                                .bounds([window.0 as f64, window.1 as f64])
+                                .labels(x_ticks.iter().cloned().map(Span::from).collect()),
+                        )
+                        .y_axis(
+                            Axis::default()
+                                .title(match stacked {
+                                    true => "% share".to_string(),
+                                    false => y_title(series, scale),
+                                })
+                                .style(match app.line_chart.focused() {
+                                    true => focused_style,
+                                    false => theme.muted(),
+                                })
+                                .bounds(y_bounds)
+                                .labels(y_ticks.iter().cloned().map(Span::from).collect())
+                                .labels_alignment(Alignment::Center),
+                        );
+
+                    frame.render_widget(facet_chart, chart_layouts[0]);
                 }
-                None => {}
             }
         }
 
+        if app.line_chart.stats && app.api_error.is_empty() && !app.no_results {
+            render_stats(frame, app, selected_query, chart_layouts[1]);
+        }
+
         // Used to load different total chart
         app.prev_query = selected_query.to_owned();
     }
+
+    render_chart_values(frame, app, &chart_labels, layouts[1]);
+
+    // Draw completion popup last to be on top of other widgets
+    render_completion(
+        frame,
+        &app.search_input,
+        search_layouts[1],
+        layouts[1],
+        theme,
+    );
+    render_completion(
+        frame,
+        &app.facets_input,
+        facet_layouts[1],
+        layouts[1],
+        theme,
+    );
+    render_export_dialog(
+        frame,
+        &mut app.export_dialog,
+        layouts[1],
+        &state.keymap,
+        theme,
+    );
+}
+
+// Points of the visible months, X values are still the month indexes
+fn visible_points(data: &[(f64, f64)], (start, end): (usize, usize)) -> Vec<(f64, f64)> {
+    data.iter()
+        .filter(|(x, _)| *x >= start as f64 && *x <= end as f64)
+        .copied()
+        .collect()
+}
+
+// Scales of the points and of the Y axis ticks, changes are drawn as is as they could be negative
+fn series_scales(series: Series, scale: YScale) -> (YScale, YScale) {
+    match series {
+        Series::MonthOverMonth => (YScale::Linear, YScale::Linear),
+        Series::YearOverYear => (YScale::Linear, YScale::Percent),
+        _ => (scale, scale),
+    }
+}
+
+// Y axis title of the derived series and the scale, e.g. 3-month average, log
+fn y_title(series: Series, scale: YScale) -> String {
+    [series.title().as_str(), scale.title()]
+        .iter()
+        .filter(|title| !title.is_empty())
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn max_y(points: &[(f64, f64)]) -> f64 {
+    points.iter().map(|point| point.1).fold(0.0, f64::max)
+}
+
+fn min_y(points: &[(f64, f64)]) -> f64 {
+    points.iter().map(|point| point.1).fold(f64::MAX, f64::min)
+}
+
+// Y axis starts from 0 on the whole history, from the lowest visible value on zoomed in,
+// indexed lines and negative changes always start from the lowest value
+fn y_axis(
+    min_y_axis: f64,
+    max_y_axis: f64,
+    zoomed: bool,
+    scale: YScale,
+) -> ([f64; 2], Vec<String>) {
+    let from_min =
+        (zoomed || scale == YScale::Indexed || min_y_axis < 0.0) && min_y_axis < max_y_axis;
+    let bounds = match from_min {
+        true => [min_y_axis, max_y_axis],
+        false => [0.0, max_y_axis],
+    };
+    let ticks = match scale {
+        YScale::Linear if from_min => y_range_ticks(min_y_axis, max_y_axis),
+        YScale::Linear => y_ticks(max_y_axis),
+        _ => [bounds[0], (bounds[0] + bounds[1]) / 2.0, bounds[1]]
+            .iter()
+            .map(|value| scale.tick(*value))
+            .collect(),
+    };
+    (bounds, ticks)
+}
+
+// Braille dots between two lines of stacked shares as the chart can't fill areas
+fn fill_area(start: usize, bottom: &[f64], top: &[f64], area: Rect) -> Vec<(f64, f64)> {
+    // Each braille cell has 2x4 dots, twice as many points to not miss dots on rounding
+    let columns = area.width as usize * 4;
+    let rows = area.height as usize * 8;
+    let last = bottom.len().min(top.len()).saturating_sub(1);
+    if columns < 2 || rows == 0 || bottom.is_empty() || top.is_empty() {
+        return vec![];
+    }
+
+    let mut points = vec![];
+    for column in 0..columns {
+        let x = column as f64 / (columns - 1) as f64 * last as f64;
+        let (month, ratio) = (x.floor() as usize, x.fract());
+        let next = (month + 1).min(last);
+        let at = |line: &[f64]| line[month] + (line[next] - line[month]) * ratio;
+
+        let mut y = at(bottom);
+        while y <= at(top) {
+            points.push((start as f64 + x, y));
+            y += 100.0 / rows as f64;
+        }
+    }
+    points
+}
+
+// Same graph area as ratatui Chart draws the lines, excludes the padding, axes and labels
+fn graph_area(area: Rect, x_labels: &[String], y_labels: &[String]) -> Rect {
+    // Chart block has padding left and top
+    let inner = Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(1),
+        area.height.saturating_sub(1),
+    );
+    // Y labels and first X label on the left of Y axis, plus the axis
+    let labels_width = y_labels
+        .iter()
+        .map(|label| label.width())
+        .chain(
+            x_labels
+                .first()
+                .map(|label| label.width().saturating_sub(1)),
+        )
+        .max()
+        .unwrap_or(0) as u16;
+    let left = labels_width.min(inner.width / 3) + 1;
+
+    // X labels and X axis below the graph
+    Rect::new(
+        inner.x + left,
+        inner.y,
+        inner.width.saturating_sub(left),
+        inner.height.saturating_sub(2),
+    )
+}
+
+// Popup to choose the format and file to export chart data
+fn render_export_dialog(
+    frame: &mut Frame<'_>,
+    dialog: &mut ExportDialog,
+    area: Rect,
+    keymap: &Keymap,
+    theme: Theme,
+) {
+    if dialog.hidden() {
+        return;
+    }
+
+    let width = area.width.min(EXPORT_DIALOG_WIDTH);
+    let height = area.height.min(6);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default()
+        .title("Export chart")
+        .borders(Borders::ALL)
+        .border_style(theme.focused())
+        .padding(Padding::new(1, 1, 0, 0));
+    let inner = block.inner(popup);
+
+    // File input after the prefix
+    let prefix = "File:   ";
+    dialog
+        .path
+        .scroll(inner.width.saturating_sub(prefix.len() as u16) as usize);
+
+    let mut formats = vec![Span::from("Format: ")];
+    for format in ExportFormat::ALL {
+        formats.push(match *format == dialog.format {
+            true => Span::styled(
+                format!(" {} ", format),
+                Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            ),
+            false => Span::from(format!(" {} ", format)),
+        });
+    }
+    let mut lines = vec![
+        Line::from(formats),
+        Line::from(vec![
+            Span::from(prefix),
+            Span::from(dialog.path.visible_input()),
+        ]),
+        Line::default(),
+    ];
+    if dialog.overwrite {
+        let warning = match keymap.chords(Action::Confirm).first() {
+            Some(chord) => format!("File exists, press {} to overwrite", chord.label()),
+            None => "File exists".to_string(),
+        };
+        lines.push(Line::from(Span::styled(warning, theme.warning())));
+    }
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+    frame.set_cursor(
+        inner.x + prefix.len() as u16 + dialog.path.cursor_column(),
+        inner.y + 1,
+    );
+    dialog.set_area(popup);
+}
+
+// Stats of the highlighted query in the visible months
+fn render_stats(frame: &mut Frame<'_>, app: &App, query: &str, area: Rect) {
+    let Some(chart) = app.charts.get(query) else {
+        return;
+    };
+    let facet = chart.facets.get(app.facet_values.tab_index);
+    let (start, end) = app.line_chart.window(app.line_chart.months());
+    let month = |index: usize| {
+        app.line_chart
+            .data
+            .get(index + 1)
+            .map_or("", |row| row[0].as_str())
+    };
+    let Some(stats) = Stats::new(chart, facet, month(start), month(end)) else {
+        return;
+    };
+
+    let row = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<8}", name), app.config.theme.muted()),
+            Span::from(value),
+        ])
+    };
+    let count = |(month, count): &(String, f64)| {
+        format!("{} {}", month, (*count as i64).human_count_bare())
+    };
+    let mut lines = vec![
+        row("Latest", count(&stats.latest)),
+        row("Min", count(&stats.min)),
+        row("Max", count(&stats.max)),
+        row(
+            "Change",
+            match stats.change_percent {
+                Some(percent) => format!(
+                    "{} ({})",
+                    format_change(stats.change),
+                    format_percent(percent)
+                ),
+                None => format_change(stats.change),
+            },
+        ),
+        row(
+            "CAGR",
+            stats.cagr.map_or("-".to_string(), |cagr| {
+                format!("{}/ year", format_percent(cagr))
+            }),
+        ),
+    ];
+
+    if let (Some(facet), false) = (facet, stats.movers.is_empty()) {
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(
+            format!("Top movers ({})", facet.name),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        // Labels are cut to fit values in the panel
+        let width = area.width.saturating_sub(2) as usize;
+        for mover in &stats.movers {
+            let value = mover.value();
+            let symbol = match mover.change > 0.0 {
+                true => "▲",
+                false => "▼",
+            };
+            let label: String = mover
+                .label
+                .chars()
+                .take(width.saturating_sub(value.width() + 3))
+                .collect();
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{} ", symbol),
+                    app.config.theme.change(mover.change > 0.0),
+                ),
+                Span::from(format!(
+                    "{:<1$} ",
+                    label,
+                    width.saturating_sub(value.width() + 3)
+                )),
+                Span::from(value),
+            ]));
+        }
+    }
+
+    let stats = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Stats: {}", decode_query(query).0)),
+    );
+    frame.render_widget(stats, area);
+}
+
+// Show values of the month under the mouse next to the cursor line
+fn render_chart_values(
+    frame: &mut Frame<'_>,
+    app: &App,
+    labels: &[(String, LineStyle)],
+    area: Rect,
+) {
+    let chart_area = app.line_chart.area();
+    let Some(row) = app
+        .line_chart
+        .cursor
+        .and_then(|cursor| app.line_chart.data.get(cursor + 1))
+    else {
+        return;
+    };
+    if chart_area.area() == 0 || labels.is_empty() {
+        return;
+    }
+
+    // Start month of the range to zoom in
+    let (start, end) = app.line_chart.window(app.line_chart.months());
+    if let Some(anchor) = app
+        .line_chart
+        .anchor
+        .filter(|anchor| (start..=end).contains(anchor))
+    {
+        let column = app.line_chart.column_of(anchor);
+        for y in chart_area.top()..chart_area.bottom() {
+            frame.buffer_mut().get_mut(column, y).set_symbol("┊");
+        }
+    }
+
+    let column = app.line_chart.column_of(app.line_chart.cursor.unwrap_or(0));
+    for y in chart_area.top()..chart_area.bottom() {
+        frame.buffer_mut().get_mut(column, y).set_symbol("│");
+    }
+
+    let lines: Vec<Line> = labels
+        .iter()
+        .zip(row.iter().skip(1))
+        .map(|((label, line_style), value)| {
+            Line::from(vec![
+                Span::styled(format!("{} ", line_style.symbol), line_style.style()),
+                // No data of the month if the query has different months range
+                Span::from(match value.is_empty() {
+                    true => format!("{}: -", label),
+                    false => format!("{}: {}", label, value),
+                }),
+            ])
+        })
+        .collect();
+    let width = lines
+        .iter()
+        .map(|line| line.width())
+        .chain([row[0].width()])
+        .max()
+        .unwrap_or(0) as u16
+        + 2;
+    let height = lines.len() as u16 + 2;
+
+    // Right of the cursor line, or left if not enough space
+    let x = match column + 1 + width <= area.right() {
+        true => column + 1,
+        false => column.saturating_sub(width).max(area.x),
+    };
+    let popup_area = Rect::new(
+        x,
+        chart_area.y,
+        width.min(area.width),
+        height.min(area.bottom().saturating_sub(chart_area.y)),
+    );
+
+    let values = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(row[0].to_owned())
+            .border_style(app.config.theme.muted()),
+    );
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(values, popup_area);
+}
+
+// Show completion items below the searchbox, start at the name being completed
+fn render_completion(
+    frame: &mut Frame<'_>,
+    input: &UserInput,
+    input_area: Rect,
+    area: Rect,
+    theme: Theme,
+) {
+    let Some((items, index, column)) = input.completion() else {
+        return;
+    };
+
+    let width = items.iter().map(|item| item.len()).max().unwrap_or(0) as u16 + 4;
+    let height = (items.len() as u16 + 2).min(10).min(area.height);
+    let x = (input_area.x + column)
+        .saturating_sub(2)
+        .min(area.right().saturating_sub(width));
+    let popup_area = Rect::new(x, area.y, width.min(area.width), height);
+
+    let list = List::new(
+        items
+            .iter()
+            .map(|item| ListItem::new(item.as_str()))
+            .collect::<Vec<ListItem>>(),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.focused()),
+    )
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default().with_selected(Some(index));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, popup_area, &mut list_state);
+}
+
+// Show search pattern on reverse searching history, e.g. (reverse-i-search)`ngi'
+fn history_search_title(app: &App) -> String {
+    let search = app
+        .search_input
+        .history_search()
+        .or(app.facets_input.history_search());
+
+    match search {
+        Some((pattern, true)) => format!("(reverse-i-search)`{}'", pattern),
+        Some((pattern, false)) => format!("(failed reverse-i-search)`{}'", pattern),
+        None => String::new(),
+    }
+}
+
+// Highlight filters, values and negations of the visible query
+fn query_line<'a>(query: &'a str, visible: &'a str, filters: &[String], theme: Theme) -> Line<'a> {
+    let offset = query.len() - visible.len();
+    let spans: Vec<Span> = query::tokenize(query, filters)
+        .into_iter()
+        .filter(|token| token.end > offset)
+        .map(|token| {
+            Span::styled(
+                &query[token.start.max(offset)..token.end],
+                theme.token(token.kind),
+            )
+        })
+        .collect();
+
+    Line::from(spans)
+}
+
+// Label of a query or facet value, monochrome lines are told apart by their markers
+fn legend_line<'a>(label: &str, line_style: &LineStyle, theme: Theme) -> Line<'a> {
+    match theme {
+        Theme::Monochrome => Line::from(format!("{} {}", line_style.symbol, label)),
+        _ => Line::from(label.to_owned()),
+    }
 }
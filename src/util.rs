@@ -3,6 +3,7 @@ use serde_json::{json, Value};
 use std::fs::{create_dir_all, metadata, set_permissions, File};
 use std::io::prelude::*;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn get_config_dir() -> String {
     let mut home_dir_str = String::new();
@@ -20,6 +21,16 @@ pub fn get_config_dir() -> String {
     home_dir_str
 }
 
+/// Directory to save application data, e.g. cache, sessions
+pub fn get_data_dir() -> String {
+    let config_dir = get_config_dir();
+
+    match config_dir.is_empty() {
+        true => config_dir,
+        false => format!("{}/strend", config_dir),
+    }
+}
+
 pub fn get_api_key() -> Result<String, std::io::Error> {
     let config_dir: String = get_config_dir();
     let mut file = File::open(format!("{}/api_key", config_dir))?;
@@ -85,15 +96,12 @@ pub fn init_api_key(mut key: String, validate: bool) -> Result<(), std::io::Erro
                                     println!("Successfully initialized");
                                 }
                                 Err(err) => {
-                                    println!(
-                                        "Error: Failed to write API key ({})",
-                                        err.to_string()
-                                    );
+                                    println!("Error: Failed to write API key ({})", err);
                                 }
                             };
                         }
                         Err(err) => {
-                            println!("Error: Failed to create API key ({})", err.to_string());
+                            println!("Error: Failed to create API key ({})", err);
                         }
                     };
                 }
@@ -108,3 +116,49 @@ pub fn init_api_key(mut key: String, validate: bool) -> Result<(), std::io::Erro
 
     Ok(())
 }
+
+/// UTC time in YYYYMMDD-HHMMSS format, e.g. 20230801-093000
+pub fn timestamp(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = utc(time);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Readable UTC time, e.g. 2023-08-01 09:30:00 UTC
+pub fn datetime(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = utc(time);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, hour, minute, second
+    )
+}
+
+// Year, month, day, hour, minute and second of the time in UTC
+fn utc(time: SystemTime) -> (i64, i64, i64, u64, u64, u64) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01, http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    )
+}
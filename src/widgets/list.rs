@@ -288,7 +288,7 @@ impl<'a> StatefulWidget for List<'a> {
             let item_style = self.style.patch(item.style);
             buf.set_style(area, item_style);
 
-            let is_selected = state.selected.map_or(false, |s| s == i);
+            let is_selected = state.selected == Some(i);
             for (j, line) in item.content.lines.iter().enumerate() {
                 // if the item is selected, we need to display the highlight symbol:
                 // - either for the first line of the item only,